@@ -0,0 +1,61 @@
+struct VertexInput {
+    @location(0) data: vec3<u32>,
+}
+
+struct PlayerUniform {
+    vp: mat4x4<f32>,
+    inv_vp: mat4x4<f32>,
+    origin: vec3<f32>,
+    forward: vec3<f32>,
+    render_distance: u32,
+    znear: f32,
+    zfar: f32,
+}
+
+struct SkyUniform {
+    sun_dir: vec3<f32>,
+    color: vec3<f32>,
+    horizon_color: vec3<f32>,
+    glow_color: vec4<f32>,
+    glow_angle: f32,
+    sun_intensity: f32,
+    light_intensity: vec3<f32>,
+}
+
+struct PushConstants {
+    m: mat4x4<f32>,
+    color: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> player: PlayerUniform;
+
+@group(1) @binding(0)
+var<uniform> sky: SkyUniform;
+
+var<push_constant> pc: PushConstants;
+
+@vertex
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    let coords = vec3(
+        f32(extractBits(vertex.data[0], 0u, 9u)),
+        f32(extractBits(vertex.data[0], 9u, 9u)),
+        f32(extractBits(vertex.data[0], 18u, 9u)),
+    ) / 16.0;
+    let face = extractBits(vertex.data[0], 27u, 2u);
+    let light_factor = mix(mix(mix(mix(0.0, 0.6, f32(face == 0u)), 1.0, f32(face == 1u)), 0.5, f32(face == 2u)), 0.8, f32(face == 3u));
+    return VertexOutput(
+        player.vp * (vec4(-player.origin, 0.0) + pc.m * vec4(coords, 1.0)),
+        unpack4x8unorm(pc.color).rgb * light_factor * sky.light_intensity,
+    );
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4(in.color, 1.0);
+}
@@ -1,5 +1,5 @@
 struct VertexInput {
-    @location(0) data: vec2<u32>,
+    @location(0) data: vec3<u32>,
 }
 
 struct PlayerUniform {
@@ -44,17 +44,17 @@ var<push_constant> pc: PushConstants;
 @vertex
 fn vs_main(vertex: VertexInput) -> VertexOutput {
     let coords = pc.chunk_coords * 16.0 + vec3(
-        f32(extractBits(vertex.data[0], 0u, 5u)),
-        f32(extractBits(vertex.data[0], 5u, 5u)),
-        f32(extractBits(vertex.data[0], 10u, 5u)),
-    );
-    let tex_idx = extractBits(vertex.data[0], 15u, 8u);
+        f32(extractBits(vertex.data[0], 0u, 9u)),
+        f32(extractBits(vertex.data[0], 9u, 9u)),
+        f32(extractBits(vertex.data[0], 18u, 9u)),
+    ) / 16.0;
+    let tex_idx = extractBits(vertex.data[2], 18u, 11u);
     let tex_coords = vec2(
-        f32(extractBits(vertex.data[0], 27u, 5u)),
-        f32(extractBits(vertex.data[1], 27u, 5u)),
-    );
-    let face = extractBits(vertex.data[0], 23u, 2u);
-    let ao = f32(extractBits(vertex.data[0], 25u, 2u));
+        f32(extractBits(vertex.data[2], 0u, 9u)),
+        f32(extractBits(vertex.data[2], 9u, 9u)),
+    ) / 16.0;
+    let face = extractBits(vertex.data[0], 27u, 2u);
+    let ao = f32(extractBits(vertex.data[0], 29u, 2u));
     let skylight = vec3(
         f32(extractBits(vertex.data[1], 0u, 4u)),
         f32(extractBits(vertex.data[1], 4u, 4u)),
@@ -1,5 +1,5 @@
 struct VertexInput {
-    @location(0) data: vec2<u32>,
+    @location(0) data: vec3<u32>,
 }
 
 struct InventoryUniform {
@@ -19,17 +19,19 @@ var<uniform> inventory: InventoryUniform;
 @vertex
 fn vs_main(vertex: VertexInput) -> VertexOutput {
     let coords = inventory.transform * vec4(
-        f32(extractBits(vertex.data[0], 0u, 5u)),
-        f32(extractBits(vertex.data[0], 5u, 5u)),
-        f32(extractBits(vertex.data[0], 10u, 5u)),
+        vec3(
+            f32(extractBits(vertex.data[0], 0u, 9u)),
+            f32(extractBits(vertex.data[0], 9u, 9u)),
+            f32(extractBits(vertex.data[0], 18u, 9u)),
+        ) / 16.0,
         1.0,
     );
-    let tex_idx = extractBits(vertex.data[0], 15u, 8u);
+    let tex_idx = extractBits(vertex.data[2], 18u, 11u);
     let tex_coords = vec2(
-        f32(extractBits(vertex.data[0], 27u, 5u)),
-        f32(extractBits(vertex.data[1], 27u, 5u)),
-    );
-    let face = extractBits(vertex.data[0], 23u, 2u);
+        f32(extractBits(vertex.data[2], 0u, 9u)),
+        f32(extractBits(vertex.data[2], 9u, 9u)),
+    ) / 16.0;
+    let face = extractBits(vertex.data[0], 27u, 2u);
     let face_light = mix(mix(mix(mix(0.0, 0.6, f32(face == 0u)), 1.0, f32(face == 1u)), 0.5, f32(face == 2u)), 0.8, f32(face == 3u));
     return VertexOutput(coords, tex_idx, tex_coords, face_light);
 }
@@ -0,0 +1,62 @@
+struct VertexInput {
+    @location(0) corner: vec2<f32>,
+}
+
+struct InstanceInput {
+    @location(1) offset: f32,
+    @location(2) index: u32,
+}
+
+struct PlayerUniform {
+    vp: mat4x4<f32>,
+    inv_vp: mat4x4<f32>,
+    origin: vec3<f32>,
+    forward: vec3<f32>,
+    render_distance: u32,
+    znear: f32,
+    zfar: f32,
+}
+
+struct PushConstants {
+    origin: vec3<f32>,
+    size: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+const ADVANCE: f32 = 0.625;
+const GRID: vec2<f32> = vec2(16.0, 6.0);
+
+@group(0) @binding(0)
+var<uniform> player: PlayerUniform;
+
+var<push_constant> pc: PushConstants;
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let right = normalize(vec3(-player.forward.z, 0.0, player.forward.x));
+    let coords = pc.origin + (right * (instance.offset + vertex.corner.x) * ADVANCE + vec3(0.0, vertex.corner.y, 0.0)) * pc.size;
+    let cell = vec2(f32(instance.index % 16u), f32(instance.index / 16u));
+    return VertexOutput(
+        player.vp * vec4(coords - player.origin, 1.0),
+        (cell + vec2(0.5 + (vertex.corner.x - 0.5) * ADVANCE, 1.0 - vertex.corner.y)) / GRID,
+    );
+}
+
+@group(1) @binding(0)
+var t_font: texture_2d<f32>;
+
+@group(1) @binding(1)
+var s_font: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if textureSample(t_font, s_font, in.tex_coords).a >= 0.5 {
+        return vec4(1.0);
+    } else {
+        discard;
+    }
+}
@@ -1,19 +1,35 @@
 #![feature(let_chains)]
 
 use clap::Parser;
-use crustcrab::client::{Client, ClientEvent};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError};
+use crustcrab::{
+    client::{Client, ClientEvent},
+    server::{
+        Handshake, ServerEvent,
+        codec::{Compression, FrameReader, FrameWriter},
+    },
+};
 use std::{
-    io::{self, BufReader, BufWriter, prelude::*},
-    net::{Shutdown, TcpStream},
+    io::{self, BufReader, BufWriter},
+    net::{Shutdown, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
     thread,
+    time::Duration,
 };
+use winit::event_loop::EventLoopProxy;
 
 #[derive(Parser)]
 struct Args {
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
     #[arg(long, default_value_t = 8008)]
-    priority_port: u16,
-    #[arg(long, default_value_t = 8009)]
     port: u16,
+    #[arg(long)]
+    no_compression: bool,
+    #[arg(long, default_value_t = 15)]
+    timeout: u64,
+    #[arg(long, default_value_t = 30)]
+    max_backoff: u64,
 }
 
 fn main() {
@@ -22,117 +38,216 @@ fn main() {
     let proxy = client.create_proxy();
 
     let args = Args::parse();
-    let priority_addr = format!("127.0.0.1:{}", args.priority_port);
-    let priority_stream = match TcpStream::connect(&priority_addr) {
-        Ok(stream) => {
-            eprintln!("[{priority_addr}] open TCP connection SUCCEDED");
-            stream
-        }
-        Err(e) => {
-            eprintln!("[{priority_addr}] open TCP connection FAILED: {e}");
-            return;
+    let addr = display_addr(&args.host, args.port);
+    let handshake = Handshake::new(if args.no_compression {
+        Compression::None
+    } else {
+        Compression::Deflate
+    });
+    let active = Mutex::new(None::<Arc<TcpStream>>);
+    let (exit_tx, exit_rx) = crossbeam_channel::bounded::<()>(0);
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            let mut backoff = Duration::from_secs(1);
+            loop {
+                let Some((stream, peer)) = connect(&args, &addr, &handshake) else {
+                    eprintln!("[{addr}] reconnecting in {}s", backoff.as_secs());
+                    if exit_rx.recv_timeout(backoff) == Err(RecvTimeoutError::Disconnected) {
+                        break;
+                    }
+                    backoff = (backoff * 2).min(Duration::from_secs(args.max_backoff));
+                    continue;
+                };
+                backoff = Duration::from_secs(1);
+
+                client_rx.try_iter().for_each(drop);
+
+                if proxy.send_event(ServerEvent::Connected).is_err() {
+                    break;
+                }
+
+                let stream = Arc::new(stream);
+                {
+                    let mut active = active.lock().unwrap_or_else(|_| unreachable!());
+                    if exit_rx.try_recv() == Err(TryRecvError::Disconnected) {
+                        break;
+                    }
+                    *active = Some(stream.clone());
+                }
+
+                run(&stream, &peer, &args, &addr, &client_tx, &client_rx, &proxy);
+
+                if exit_rx.try_recv() == Err(TryRecvError::Disconnected)
+                    || proxy.send_event(ServerEvent::Disconnected).is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        client.run();
+
+        drop(exit_tx);
+
+        if let Some(stream) = active.lock().unwrap_or_else(|_| unreachable!()).take()
+            && let Err(e) = stream.shutdown(Shutdown::Both)
+            && e.kind() != io::ErrorKind::NotConnected
+        {
+            eprintln!("[{addr}] gracefull shutdown FAILED: {e}");
         }
-    };
-    if let Err(e) = priority_stream.set_nodelay(true) {
-        eprintln!("[{priority_addr}] disable Nagle algorithm FAILED: {e}");
-    }
-    let addr = format!("127.0.0.1:{}", args.port);
-    let stream = match TcpStream::connect(&addr) {
+    });
+}
+
+fn connect(args: &Args, addr: &str, handshake: &Handshake) -> Option<(TcpStream, Handshake)> {
+    let stream = match open(args) {
         Ok(stream) => {
             eprintln!("[{addr}] open TCP connection SUCCEDED");
             stream
         }
         Err(e) => {
             eprintln!("[{addr}] open TCP connection FAILED: {e}");
-            return;
+            return None;
         }
     };
     if let Err(e) = stream.set_nodelay(true) {
         eprintln!("[{addr}] disable Nagle algorithm FAILED: {e}");
     }
 
+    if let Err(e) = stream.set_read_timeout(Some(Duration::from_secs(args.timeout))) {
+        eprintln!("[{addr}] set read timeout FAILED: {e}");
+    }
+
+    if let Err(e) = FrameWriter::new(&stream).write(handshake, Compression::None) {
+        eprintln!("[{addr}] write handshake FAILED: {e}");
+        return None;
+    }
+    let peer = match Handshake::read(&mut FrameReader::new(&stream)) {
+        Ok(Ok(peer)) => {
+            if let Err(e) = handshake.check(&peer) {
+                eprintln!("[{addr}] handshake FAILED: {e}");
+                return None;
+            }
+            eprintln!("[{addr}] handshake SUCCEDED");
+            peer
+        }
+        Ok(Err(e)) => {
+            eprintln!("[{addr}] read handshake FAILED: {e}");
+            return None;
+        }
+        Err(e) => {
+            eprintln!("[{addr}] read handshake FAILED: {e}");
+            return None;
+        }
+    };
+    Some((stream, peer))
+}
+
+fn open(args: &Args) -> io::Result<TcpStream> {
+    let timeout = Duration::from_secs(args.timeout);
+    let mut last_e = None;
+    for addr in (&*args.host, args.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_e = Some(e),
+        }
+    }
+    Err(last_e.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no addresses found")))
+}
+
+fn run(
+    stream: &TcpStream,
+    peer: &Handshake,
+    args: &Args,
+    addr: &str,
+    client_tx: &Sender<ClientEvent>,
+    client_rx: &Receiver<ClientEvent>,
+    proxy: &EventLoopProxy<ServerEvent>,
+) {
+    let remap = peer.block_remap();
+
     thread::scope(|s| {
         s.spawn(|| {
-            let mut priority_reader = BufReader::new(&priority_stream);
+            let mut reader = FrameReader::new(BufReader::new(stream));
             loop {
-                let event = match bincode::deserialize_from(&mut priority_reader) {
-                    Ok(event) => event,
+                let mut event = match reader.read() {
+                    Ok(Ok(ServerEvent::Ping)) => {
+                        _ = client_tx.send(ClientEvent::Pong);
+                        continue;
+                    }
+                    Ok(Ok(ServerEvent::SessionClosed(reason))) => {
+                        eprintln!("[{addr}] session CLOSED by server: {reason}");
+                        break;
+                    }
+                    Ok(Ok(event)) => event,
+                    Ok(Err(e)) => {
+                        eprintln!("[{addr}] read server event FAILED: {e}");
+                        continue;
+                    }
                     Err(e) => {
-                        if let bincode::ErrorKind::Io(e) = &*e
-                            && e.kind() == io::ErrorKind::UnexpectedEof
-                        {
-                            _ = client_tx.send(ClientEvent::ServerDisconnected);
-                            break;
+                        match e.kind() {
+                            io::ErrorKind::UnexpectedEof => {}
+                            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                                eprintln!(
+                                    "[{addr}] read server event FAILED: no response in {}s",
+                                    args.timeout,
+                                );
+                            }
+                            _ => eprintln!("[{addr}] read server event FAILED: {e}"),
                         }
-                        eprintln!("[{priority_addr}] read server event FAILED: {e}");
-                        continue;
+                        break;
                     }
                 };
+                if let Some(remap) = &remap {
+                    event.remap_blocks(remap);
+                }
                 if proxy.send_event(event).is_err() {
                     break;
                 }
             }
-            eprintln!("[{priority_addr}] reading CLOSED");
+            _ = client_tx.send(ClientEvent::ServerDisconnected);
+            eprintln!("[{addr}] reading CLOSED: {}", reader.stats());
         });
 
         s.spawn(|| {
-            let mut priority_writer = BufWriter::new(&priority_stream);
+            let mut writer = FrameWriter::new(BufWriter::new(stream));
             for event in client_rx {
                 if matches!(event, ClientEvent::ServerDisconnected) {
                     break;
                 }
-                if let Err(e) = bincode::serialize_into(&mut priority_writer, &event) {
-                    if let bincode::ErrorKind::Io(e) = &*e
-                        && e.kind() == io::ErrorKind::BrokenPipe
-                    {
-                        break;
-                    }
-                    eprintln!("[{priority_addr}] write client event FAILED: {e}");
+                let event = match &remap {
+                    Some(remap) => event.remap_blocks(remap),
+                    None => Some(event),
+                };
+                let Some(event) = event else {
                     continue;
-                }
-                if let Err(e) = priority_writer.flush() {
-                    if e.kind() == io::ErrorKind::BrokenPipe {
-                        break;
-                    }
-                    eprintln!("[{priority_addr}] write client event FAILED: {e}");
-                }
-            }
-            eprintln!("[{priority_addr}] writing CLOSED");
-        });
-
-        s.spawn(|| {
-            let mut reader = BufReader::new(&stream);
-            loop {
-                let event = match bincode::deserialize_from(&mut reader) {
-                    Ok(event) => event,
-                    Err(e) => {
-                        if let bincode::ErrorKind::Io(e) = &*e
-                            && e.kind() == io::ErrorKind::UnexpectedEof
-                        {
-                            break;
-                        }
-                        eprintln!("[{addr}] read server event FAILED: {e}");
-                        continue;
-                    }
                 };
-                if proxy.send_event(event).is_err() {
+                if let Err(e) = writer
+                    .write(&event, Compression::None)
+                    .and_then(|()| writer.flush())
+                {
+                    if e.kind() != io::ErrorKind::BrokenPipe {
+                        eprintln!("[{addr}] write client event FAILED: {e}");
+                    }
+                    _ = stream.shutdown(Shutdown::Both);
                     break;
                 }
             }
-            eprintln!("[{addr}] reading CLOSED");
+            eprintln!("[{addr}] writing CLOSED");
         });
+    });
 
-        client.run();
+    if let Err(e) = stream.shutdown(Shutdown::Both)
+        && e.kind() != io::ErrorKind::NotConnected
+    {
+        eprintln!("[{addr}] gracefull shutdown FAILED: {e}");
+    }
+}
 
-        if let Err(e) = priority_stream.shutdown(Shutdown::Both) {
-            if e.kind() != io::ErrorKind::NotConnected {
-                eprintln!("[{priority_addr}] gracefull shutdown FAILED: {e}");
-            }
-        }
-        if let Err(e) = stream.shutdown(Shutdown::Both) {
-            if e.kind() != io::ErrorKind::NotConnected {
-                eprintln!("[{addr}] gracefull shutdown FAILED: {e}");
-            }
-        }
-    });
+fn display_addr(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
 }
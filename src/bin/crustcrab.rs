@@ -1,14 +1,33 @@
 use crustcrab::{
-    client::Client,
-    server::{Server, ServerSender},
+    client::{Client, ClientEvent},
+    server::{ClientId, Server, ServerEvent, ServerSender},
 };
 use std::thread;
 
 fn main() {
     let (client_tx, client_rx) = crossbeam_channel::unbounded();
+    let (server_client_tx, server_client_rx) = crossbeam_channel::unbounded();
     let client = Client::new(client_tx);
+    let id = ClientId::random();
     let proxy = client.create_proxy();
-    let mut server = Server::new(ServerSender::Proxy(proxy), client_rx);
+    server_client_tx
+        .send((
+            id,
+            ClientEvent::Connected(ServerSender::Proxy(proxy).into()),
+        ))
+        .unwrap_or_else(|_| unreachable!());
+    client
+        .create_proxy()
+        .send_event(ServerEvent::Connected)
+        .unwrap_or_else(|_| unreachable!());
+    let mut server = Server::new(server_client_rx, None);
     thread::spawn(move || server.run());
+    thread::spawn(move || {
+        for event in client_rx {
+            if server_client_tx.send((id, event)).is_err() {
+                break;
+            }
+        }
+    });
     client.run();
 }
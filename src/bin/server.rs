@@ -1,43 +1,55 @@
 #![feature(let_chains)]
 
-use clap::Parser;
+use clap::{CommandFactory, Parser, error::ErrorKind};
 use crustcrab::{
     client::ClientEvent,
-    server::{Server, ServerEvent, ServerSender},
+    server::{
+        ClientId, Handshake, Server, ServerEvent, ServerSender,
+        codec::{Compression, FrameReader, FrameWriter},
+    },
 };
 use std::{
-    io::{self, BufReader, BufWriter, prelude::*},
-    net::TcpListener,
+    io::{self, BufReader, BufWriter},
+    net::{Shutdown, TcpListener},
     thread,
+    time::Duration,
 };
 
 #[derive(Parser)]
 struct Args {
+    #[arg(long, default_value = "127.0.0.1")]
+    bind: String,
     #[arg(long, default_value_t = 8008)]
-    priority_port: u16,
-    #[arg(long, default_value_t = 8009)]
     port: u16,
+    #[arg(long)]
+    seed: Option<u32>,
+    #[arg(long)]
+    no_compression: bool,
+    #[arg(long, default_value_t = 5)]
+    keepalive: u64,
+    #[arg(long, default_value_t = 15)]
+    timeout: u64,
 }
 
 fn main() {
+    let args = Args::parse();
+    if args.keepalive >= args.timeout {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "--keepalive ({}s) must be shorter than --timeout ({}s)",
+                    args.keepalive, args.timeout,
+                ),
+            )
+            .exit();
+    }
     let (client_tx, client_rx) = crossbeam_channel::unbounded();
-    let mut server = Server::new(ServerSender::disconnected(), client_rx);
+    let mut server = Server::new(client_rx, args.seed);
 
     thread::spawn(move || {
-        let args = Args::parse();
-        let priority_addr = format!("127.0.0.1:{}", args.priority_port);
-        let priority_listener = match TcpListener::bind(&priority_addr) {
-            Ok(listener) => {
-                eprintln!("[{priority_addr}] create TCP listener SUCCEDED");
-                listener
-            }
-            Err(e) => {
-                eprintln!("[{priority_addr}] create TCP listener FAILED: {e}");
-                return;
-            }
-        };
-        let addr = format!("127.0.0.1:{}", args.port);
-        let listener = match TcpListener::bind(&addr) {
+        let addr = display_addr(&args.bind, args.port);
+        let listener = match TcpListener::bind((&*args.bind, args.port)) {
             Ok(listener) => {
                 eprintln!("[{addr}] create TCP listener SUCCEDED");
                 listener
@@ -48,20 +60,7 @@ fn main() {
             }
         };
 
-        for (priority_stream, stream) in priority_listener.incoming().zip(listener.incoming()) {
-            let priority_stream = match priority_stream {
-                Ok(stream) => {
-                    eprintln!("[{priority_addr}] open TCP connection SUCCEDED");
-                    stream
-                }
-                Err(e) => {
-                    eprintln!("[{priority_addr}] open TCP connection FAILED: {e}");
-                    continue;
-                }
-            };
-            if let Err(e) = priority_stream.set_nodelay(true) {
-                eprintln!("[{priority_addr}] disable Nagle algorithm FAILED: {e}");
-            }
+        for stream in listener.incoming() {
             let stream = match stream {
                 Ok(stream) => {
                     eprintln!("[{addr}] open TCP connection SUCCEDED");
@@ -76,93 +75,147 @@ fn main() {
                 eprintln!("[{addr}] disable Nagle algorithm FAILED: {e}");
             }
 
-            let (priority_server_tx, priority_server_rx) = crossbeam_channel::unbounded();
-            let (server_tx, server_rx) = crossbeam_channel::unbounded();
-            let server_tx = ServerSender::Sender {
-                priority_tx: priority_server_tx,
-                tx: server_tx,
-            };
-            client_tx
-                .send(ClientEvent::Connected(server_tx.clone().into()))
-                .unwrap_or_else(|_| unreachable!());
+            if let Err(e) = stream.set_read_timeout(Some(Duration::from_secs(args.timeout))) {
+                eprintln!("[{addr}] set read timeout FAILED: {e}");
+            }
 
-            thread::scope(|s| {
-                s.spawn(|| {
-                    let mut priority_writer = BufWriter::new(&priority_stream);
-                    for event in &priority_server_rx {
-                        if matches!(event, ServerEvent::ClientDisconnected) {
-                            break;
-                        }
-                        if let Err(e) = bincode::serialize_into(&mut priority_writer, &event) {
-                            if let bincode::ErrorKind::Io(e) = &*e
-                                && e.kind() == io::ErrorKind::BrokenPipe
-                            {
-                                break;
-                            }
-                            eprintln!("[{priority_addr}] write server event FAILED: {e}");
-                            continue;
+            let client_tx = client_tx.clone();
+            let addr = addr.clone();
+            thread::spawn(move || {
+                let handshake = Handshake::new(if args.no_compression {
+                    Compression::None
+                } else {
+                    Compression::Deflate
+                });
+                let compression = match Handshake::read(&mut FrameReader::new(&stream)) {
+                    Ok(Ok(peer)) => {
+                        if let Err(e) =
+                            FrameWriter::new(&stream).write(&handshake, Compression::None)
+                        {
+                            eprintln!("[{addr}] write handshake FAILED: {e}");
+                            return;
                         }
-                        if let Err(e) = priority_writer.flush() {
-                            if e.kind() == io::ErrorKind::BrokenPipe {
-                                break;
-                            }
-                            eprintln!("[{priority_addr}] write server event FAILED: {e}");
+                        if let Err(e) = handshake.check(&peer) {
+                            eprintln!("[{addr}] handshake FAILED: {e}");
+                            return;
                         }
+                        eprintln!("[{addr}] handshake SUCCEDED");
+                        handshake.compression(&peer)
                     }
-                    eprintln!("[{priority_addr}] writing CLOSED");
-                });
+                    Ok(Err(e)) => {
+                        eprintln!("[{addr}] read handshake FAILED: {e}");
+                        return;
+                    }
+                    Err(e) => {
+                        eprintln!("[{addr}] read handshake FAILED: {e}");
+                        return;
+                    }
+                };
 
-                s.spawn(|| {
-                    let mut writer = BufWriter::new(&stream);
-                    for event in &server_rx {
-                        if matches!(event, ServerEvent::ClientDisconnected) {
-                            break;
-                        }
-                        if let Err(e) = bincode::serialize_into(&mut writer, &event) {
-                            if let bincode::ErrorKind::Io(e) = &*e
-                                && e.kind() == io::ErrorKind::BrokenPipe
-                            {
+                let id = ClientId::random();
+                let (priority_server_tx, priority_server_rx) = crossbeam_channel::unbounded();
+                let (server_tx, server_rx) = crossbeam_channel::unbounded();
+                let server_tx = ServerSender::Sender {
+                    priority_tx: priority_server_tx,
+                    tx: server_tx,
+                };
+                if client_tx
+                    .send((id, ClientEvent::Connected(server_tx.clone().into())))
+                    .is_err()
+                {
+                    return;
+                }
+
+                thread::scope(|s| {
+                    s.spawn(|| {
+                        let mut writer = FrameWriter::new(BufWriter::new(&stream));
+                        let keepalive =
+                            crossbeam_channel::tick(Duration::from_secs(args.keepalive));
+                        loop {
+                            let event = crossbeam_channel::select_biased! {
+                                recv(keepalive) -> _ => Ok(ServerEvent::Ping),
+                                recv(priority_server_rx) -> event => event,
+                                recv(server_rx) -> event => event,
+                            };
+                            let Ok(event) = event else {
+                                break;
+                            };
+                            if matches!(event, ServerEvent::ClientDisconnected) {
                                 break;
                             }
-                            eprintln!("[{addr}] write server event FAILED: {e}");
-                            continue;
-                        }
-                        if let Err(e) = writer.flush() {
-                            if e.kind() == io::ErrorKind::BrokenPipe {
+                            let compression = if event.has_priority() {
+                                Compression::None
+                            } else {
+                                compression
+                            };
+                            let result = writer.write(&event, compression).and_then(|()| {
+                                if priority_server_rx.is_empty() && server_rx.is_empty() {
+                                    writer.flush()
+                                } else {
+                                    Ok(())
+                                }
+                            });
+                            if let Err(e) = result {
+                                if e.kind() != io::ErrorKind::BrokenPipe {
+                                    eprintln!("[{addr}] write server event FAILED: {e}");
+                                    _ = writer
+                                        .write(
+                                            &ServerEvent::SessionClosed(e.to_string()),
+                                            Compression::None,
+                                        )
+                                        .and_then(|()| writer.flush());
+                                }
+                                _ = stream.shutdown(Shutdown::Both);
                                 break;
                             }
-                            eprintln!("[{addr}] write server event FAILED: {e}");
                         }
-                    }
-                    eprintln!("[{addr}] writing CLOSED");
-                });
+                        eprintln!("[{addr}] writing CLOSED: {}", writer.stats());
+                    });
 
-                let mut priority_reader = BufReader::new(&priority_stream);
-                loop {
-                    let event = match bincode::deserialize_from(&mut priority_reader) {
-                        Ok(event) => event,
-                        Err(e) => {
-                            if let bincode::ErrorKind::Io(e) = &*e
-                                && let io::ErrorKind::ConnectionReset | io::ErrorKind::UnexpectedEof =
-                                    e.kind()
-                            {
-                                server_tx
-                                    .send(ServerEvent::ClientDisconnected)
-                                    .unwrap_or_else(|_| unreachable!());
+                    let mut reader = FrameReader::new(BufReader::new(&stream));
+                    loop {
+                        let event = match reader.read() {
+                            Ok(Ok(ClientEvent::Pong)) => continue,
+                            Ok(Ok(event)) => event,
+                            Ok(Err(e)) => {
+                                eprintln!("[{addr}] read client event FAILED: {e}");
+                                continue;
+                            }
+                            Err(e) => {
+                                let reason = match e.kind() {
+                                    io::ErrorKind::ConnectionReset
+                                    | io::ErrorKind::UnexpectedEof => None,
+                                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                                        Some(format!("no response in {}s", args.timeout))
+                                    }
+                                    _ => Some(e.to_string()),
+                                };
+                                if let Some(reason) = reason {
+                                    eprintln!("[{addr}] read client event FAILED: {reason}");
+                                    _ = server_tx.send(ServerEvent::SessionClosed(reason));
+                                }
+                                _ = server_tx.send(ServerEvent::ClientDisconnected);
+                                _ = client_tx.send((id, ClientEvent::Disconnected));
                                 break;
                             }
-                            eprintln!("[{priority_addr}] read client event FAILED: {e}");
-                            continue;
+                        };
+                        if client_tx.send((id, event)).is_err() {
+                            break;
                         }
-                    };
-                    if client_tx.send(event).is_err() {
-                        break;
                     }
-                }
-                eprintln!("[{priority_addr}] reading CLOSED");
+                    eprintln!("[{addr}] reading CLOSED");
+                })
             });
         }
     });
 
     server.run();
 }
+
+fn display_addr(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
@@ -13,7 +13,7 @@ use crate::{
             uniform::Uniform,
         },
     },
-    server::game::world::block::{Block, data::STR_TO_BLOCK},
+    server::game::world::block::Block,
 };
 use arrayvec::ArrayVec;
 use bytemuck::{Pod, Zeroable};
@@ -22,7 +22,6 @@ use serde::{Deserialize, Deserializer};
 use std::{
     f32::consts::{FRAC_PI_4, FRAC_PI_6},
     mem,
-    ops::Deref,
 };
 use winit::{
     event::{ElementState, KeyEvent, WindowEvent},
@@ -198,21 +197,7 @@ impl InventoryConfig {
     {
         ArrayVec::<String, 9>::deserialize(deserializer)?
             .into_iter()
-            .map(|str| {
-                STR_TO_BLOCK.get(&*str).copied().ok_or_else(|| {
-                    serde::de::Error::invalid_value(
-                        serde::de::Unexpected::Str(&str),
-                        &&*format!(
-                            "one of \"{}\"",
-                            STR_TO_BLOCK
-                                .keys()
-                                .map(Deref::deref)
-                                .collect::<Vec<_>>()
-                                .join("\", \"")
-                        ),
-                    )
-                })
-            })
+            .map(|str| Block::from_name(&str))
             .collect()
     }
 }
@@ -1,9 +1,11 @@
 pub mod crosshair;
 pub mod inventory;
+pub mod status;
 
 use self::{
     crosshair::{Crosshair, CrosshairConfig},
     inventory::{Inventory, InventoryConfig},
+    status::{Status, StatusConfig},
 };
 use crate::{
     client::{
@@ -22,6 +24,7 @@ pub struct Gui {
     blit: Blit,
     crosshair: Crosshair,
     inventory: Inventory,
+    status: Status,
 }
 
 impl Gui {
@@ -34,6 +37,7 @@ impl Gui {
             blit: Blit::new(renderer, input_bind_group_layout, PostProcessor::FORMAT),
             crosshair: Crosshair::new(renderer, input_bind_group_layout),
             inventory: Inventory::new(renderer, textures_bind_group_layout),
+            status: Status::new(renderer),
         }
     }
 
@@ -41,6 +45,10 @@ impl Gui {
         self.inventory.selected_block()
     }
 
+    pub fn is_connected(&self) -> bool {
+        !self.status.is_disconnected()
+    }
+
     pub fn draw(
         &self,
         view: &wgpu::TextureView,
@@ -63,6 +71,7 @@ impl Gui {
             });
             self.blit.draw(&mut render_pass, input_bind_group);
             self.crosshair.draw(&mut render_pass, input_bind_group);
+            self.status.draw(&mut render_pass);
         }
         self.inventory.draw(
             &mut encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -107,6 +116,7 @@ impl EventHandler for Gui {
     fn handle(&mut self, event: &Event, renderer: Self::Context<'_>) {
         self.crosshair.handle(event, renderer);
         self.inventory.handle(event, renderer);
+        self.status.handle(event, renderer);
     }
 }
 
@@ -114,4 +124,5 @@ impl EventHandler for Gui {
 pub struct GuiConfig {
     crosshair: CrosshairConfig,
     inventory: InventoryConfig,
+    status: StatusConfig,
 }
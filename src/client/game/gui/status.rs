@@ -0,0 +1,130 @@
+use super::Gui;
+use crate::{
+    client::{
+        CLIENT_CONFIG,
+        event_loop::{Event, EventHandler},
+        game::players::{CORNERS, FONT_PATH, GlyphInstance, LabelVertex},
+        renderer::{
+            Renderer,
+            buffer::{Instance, InstanceBuffer, MemoryState, Vertex, VertexBuffer},
+            effect::PostProcessor,
+            program::Program,
+            texture::image::ImageTexture,
+            uniform::Uniform,
+        },
+    },
+    server::ServerEvent,
+};
+use bytemuck::{Pod, Zeroable};
+use nalgebra::{Matrix4, vector};
+use serde::Deserialize;
+
+pub struct Status {
+    uniform: Uniform<StatusUniformData>,
+    vertex_buffer: VertexBuffer<LabelVertex>,
+    instance_buffer: InstanceBuffer<GlyphInstance>,
+    texture: ImageTexture,
+    program: Program,
+    is_disconnected: bool,
+}
+
+impl Status {
+    const DISCONNECTED: &str = "Connecting to server...";
+
+    pub fn new(renderer: &Renderer) -> Self {
+        let uniform = Uniform::new(renderer, MemoryState::UNINIT, wgpu::ShaderStages::VERTEX);
+        let texture = ImageTexture::new(
+            renderer,
+            FONT_PATH,
+            1,
+            false,
+            wgpu::AddressMode::ClampToEdge,
+        );
+        let program = Program::new(
+            renderer,
+            wgpu::include_wgsl!("../../../../assets/shaders/status.wgsl"),
+            &[LabelVertex::desc(), GlyphInstance::desc()],
+            &[uniform.bind_group_layout(), texture.bind_group_layout()],
+            &[],
+            None,
+            None,
+            PostProcessor::FORMAT,
+            None,
+        );
+        let len = Self::DISCONNECTED.chars().count() as f32;
+        Self {
+            uniform,
+            vertex_buffer: VertexBuffer::new(
+                renderer,
+                MemoryState::Immutable(&CORNERS.map(LabelVertex::new)),
+            ),
+            instance_buffer: InstanceBuffer::new(
+                renderer,
+                MemoryState::Immutable(
+                    &Self::DISCONNECTED
+                        .chars()
+                        .enumerate()
+                        .map(|(i, c)| GlyphInstance::new(i as f32 - len / 2.0, c))
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+            texture,
+            program,
+            is_disconnected: true,
+        }
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.is_disconnected
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        if self.is_disconnected {
+            self.program.bind(
+                render_pass,
+                [self.uniform.bind_group(), self.texture.bind_group()],
+            );
+            self.vertex_buffer
+                .draw_instanced(render_pass, &self.instance_buffer);
+        }
+    }
+}
+
+impl EventHandler for Status {
+    type Context<'a> = &'a Renderer;
+
+    fn handle(&mut self, event: &Event, renderer: Self::Context<'_>) {
+        match event {
+            Event::UserEvent(ServerEvent::Disconnected) => self.is_disconnected = true,
+            Event::UserEvent(ServerEvent::Connected) => self.is_disconnected = false,
+            _ => {}
+        }
+
+        if renderer.is_resized {
+            self.uniform
+                .set(renderer, &StatusUniformData::new(renderer));
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod)]
+struct StatusUniformData {
+    transform: Matrix4<f32>,
+}
+
+impl StatusUniformData {
+    fn new(renderer: &Renderer) -> Self {
+        Self {
+            transform: Gui::transform(
+                Gui::scaling(renderer, CLIENT_CONFIG.gui.status.size),
+                vector![0.5, 0.85],
+            ),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StatusConfig {
+    size: f32,
+}
@@ -18,6 +18,7 @@ use crate::{
 };
 use bytemuck::{Pod, Zeroable};
 use nalgebra::{Matrix4, Point3, Vector3, vector};
+use std::array;
 
 pub struct BlockHover {
     highlight: BlockHighlight,
@@ -48,7 +49,7 @@ impl BlockHover {
         sky_bind_group: &wgpu::BindGroup,
         depth_view: &wgpu::TextureView,
     ) {
-        if let Some(BlockHoverData { hitbox, brightness }) = self.data {
+        if let Some(BlockHoverData { brightness, .. }) = self.data {
             self.highlight.draw(
                 &mut encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -71,25 +72,32 @@ impl BlockHover {
                 }),
                 player_bind_group,
                 sky_bind_group,
-                &BlockHighlightPushConstants::new(hitbox, brightness),
+                &BlockHighlightPushConstants::new(self.highlight.origin, brightness),
             );
         }
     }
 }
 
 impl EventHandler for BlockHover {
-    type Context<'a> = ();
+    type Context<'a> = &'a Renderer;
 
-    fn handle(&mut self, event: &Event, (): Self::Context<'_>) {
-        if let Event::UserEvent(ServerEvent::BlockHovered(data)) = *event {
-            self.data = data;
+    fn handle(&mut self, event: &Event, renderer: Self::Context<'_>) {
+        match event {
+            Event::UserEvent(ServerEvent::BlockHovered(data)) => {
+                if let Some(BlockHoverData { hitboxes, .. }) = data {
+                    self.highlight.set(renderer, hitboxes);
+                }
+                self.data = data.clone();
+            }
+            Event::UserEvent(ServerEvent::Disconnected) => self.data = None,
+            _ => {}
         }
     }
 }
 
 struct BlockHighlight {
-    vertex_buffer: VertexBuffer<BlockHighlightVertex>,
-    index_buffer: IndexBuffer<u16>,
+    buffers: Option<(VertexBuffer<BlockHighlightVertex>, IndexBuffer<u16>)>,
+    origin: Point3<f32>,
     program: Program,
 }
 
@@ -100,11 +108,8 @@ impl BlockHighlight {
         sky_bind_group_layout: &wgpu::BindGroupLayout,
     ) -> Self {
         Self {
-            vertex_buffer: VertexBuffer::new(
-                renderer,
-                MemoryState::Immutable(&DELTAS.map(BlockHighlightVertex::new)),
-            ),
-            index_buffer: IndexBuffer::new(renderer, MemoryState::Immutable(&INDICES)),
+            buffers: None,
+            origin: Default::default(),
             program: Program::new(
                 renderer,
                 wgpu::include_wgsl!("../../../assets/shaders/highlight.wgsl"),
@@ -133,9 +138,86 @@ impl BlockHighlight {
         sky_bind_group: &wgpu::BindGroup,
         pc: &BlockHighlightPushConstants,
     ) {
-        self.program.bind(render_pass, [player_bind_group, sky_bind_group]);
-        pc.set(render_pass);
-        self.vertex_buffer.draw_indexed(render_pass, &self.index_buffer);
+        if let Some((vertex_buffer, index_buffer)) = &self.buffers {
+            self.program.bind(render_pass, [player_bind_group, sky_bind_group]);
+            pc.set(render_pass);
+            vertex_buffer.draw_indexed(render_pass, index_buffer);
+        }
+    }
+
+    fn set(&mut self, renderer: &Renderer, hitboxes: &[Aabb]) {
+        let hitboxes = hitboxes
+            .iter()
+            .map(|hitbox| hitbox.pad(CLIENT_CONFIG.cloud.padding))
+            .collect::<Vec<_>>();
+
+        let Some(origin) = hitboxes.first().map(|hitbox| hitbox.min) else {
+            self.buffers = None;
+            return;
+        };
+
+        let bounds = array::from_fn(|i| {
+            let mut bounds = hitboxes
+                .iter()
+                .flat_map(|hitbox| [hitbox.min[i], hitbox.max[i]])
+                .collect::<Vec<_>>();
+            bounds.sort_unstable_by(f32::total_cmp);
+            bounds.dedup();
+            bounds
+        });
+
+        let is_filled = |coords| {
+            Self::cell(&bounds, coords).is_some_and(|cell| {
+                let center = cell.min + (cell.max - cell.min) * 0.5;
+                hitboxes.iter().any(|hitbox| hitbox.contains(center))
+            })
+        };
+
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        for x in 0..bounds[0].len() as i32 {
+            for y in 0..bounds[1].len() as i32 {
+                for z in 0..bounds[2].len() as i32 {
+                    let coords = Point3::new(x, y, z);
+                    if !is_filled(coords) {
+                        continue;
+                    }
+
+                    let cell = Self::cell(&bounds, coords).unwrap_or_else(|| unreachable!());
+                    for (face, normal) in INDICES.chunks_exact(6).zip(NORMALS) {
+                        if !is_filled(coords + normal) {
+                            let len = vertices.len() as u16;
+                            vertices.extend([face[0], face[1], face[2], face[5]].map(|i| {
+                                BlockHighlightVertex::new(
+                                    cell.min - origin
+                                        + DELTAS[i as usize].component_mul(&(cell.max - cell.min)),
+                                )
+                            }));
+                            indices.extend([0, 1, 2, 0, 2, 3].map(|i| len + i));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.buffers = VertexBuffer::new_non_empty(renderer, MemoryState::Immutable(&vertices))
+            .map(|vertex_buffer| {
+                (
+                    vertex_buffer,
+                    IndexBuffer::new(renderer, MemoryState::Immutable(&indices)),
+                )
+            });
+        self.origin = origin;
+    }
+
+    fn cell(bounds: &[Vec<f32>; 3], coords: Point3<i32>) -> Option<Aabb> {
+        let mut cell = Aabb::default();
+        for i in 0..3 {
+            let index = usize::try_from(coords[i]).ok()?;
+            cell.min[i] = *bounds[i].get(index)?;
+            cell.max[i] = *bounds[i].get(index + 1)?;
+        }
+        Some(cell)
     }
 }
 
@@ -165,16 +247,12 @@ struct BlockHighlightPushConstants {
 }
 
 impl BlockHighlightPushConstants {
-    fn new(hitbox: Aabb, brightness: BlockLight) -> Self {
+    fn new(origin: Point3<f32>, brightness: BlockLight) -> Self {
         Self {
-            m: Self::m(hitbox),
+            m: Matrix4::new_translation(&origin.coords),
             brightness: brightness.0,
         }
     }
-
-    fn m(hitbox: Aabb) -> Matrix4<f32> {
-        hitbox.pad(CLIENT_CONFIG.cloud.padding).to_homogeneous()
-    }
 }
 
 impl PushConstants for BlockHighlightPushConstants {
@@ -192,6 +270,15 @@ const DELTAS: [Vector3<f32>; 8] = [
     vector![0.0, 1.0, 1.0],
 ];
 
+const NORMALS: [Vector3<i32>; 6] = [
+    vector![0, 0, -1],
+    vector![1, 0, 0],
+    vector![0, 0, 1],
+    vector![-1, 0, 0],
+    vector![0, 1, 0],
+    vector![0, -1, 0],
+];
+
 #[rustfmt::skip]
 const INDICES: [u16; 36] = [
     0, 1, 2, 0, 2, 3,
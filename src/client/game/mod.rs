@@ -3,12 +3,13 @@ pub mod fog;
 pub mod gui;
 pub mod hover;
 pub mod player;
+pub mod players;
 pub mod sky;
 pub mod world;
 
 use self::{
-    cloud::CloudLayer, fog::Fog, gui::Gui, hover::BlockHover, player::Player, sky::Sky,
-    world::World,
+    cloud::CloudLayer, fog::Fog, gui::Gui, hover::BlockHover, player::Player, players::Players,
+    sky::Sky, world::World,
 };
 use super::{
     ClientEvent,
@@ -31,6 +32,7 @@ pub struct Game {
     clouds: CloudLayer,
     fog: Fog,
     hover: BlockHover,
+    players: Players,
     aces: Aces,
     gui: Gui,
     player: Player,
@@ -68,6 +70,11 @@ impl Game {
             player.bind_group_layout(),
             sky.bind_group_layout(),
         );
+        let players = Players::new(
+            renderer,
+            player.bind_group_layout(),
+            sky.bind_group_layout(),
+        );
         let aces = Aces::new(
             renderer,
             processor.bind_group_layout(),
@@ -84,6 +91,7 @@ impl Game {
             clouds,
             fog,
             hover,
+            players,
             aces,
             gui,
             player,
@@ -112,6 +120,14 @@ impl Game {
             self.depth.view(),
             &self.player.frustum(),
             |encoder| {
+                self.players.draw(
+                    self.fog.view(),
+                    encoder,
+                    self.player.bind_group(),
+                    self.sky.bind_group(),
+                    self.depth.view(),
+                );
+
                 self.fog.draw(
                     self.processor.view(),
                     encoder,
@@ -199,7 +215,8 @@ impl EventHandler for Game {
         self.world.handle(event, renderer);
         self.clouds.handle(event, dt);
         self.fog.handle(event, renderer);
-        self.hover.handle(event, ());
+        self.hover.handle(event, renderer);
+        self.players.handle(event, (renderer, dt));
         self.gui.handle(event, renderer);
         self.player.handle(event, (client_tx, renderer, &self.gui, dt));
         self.depth.handle(event, renderer);
@@ -230,6 +247,15 @@ struct BlockTextureArray(ImageTextureArray);
 
 impl BlockTextureArray {
     fn new(renderer: &Renderer) -> Self {
+        let max_count = renderer
+            .device
+            .limits()
+            .max_sampled_textures_per_shader_stage as usize;
+        assert!(
+            TEX_PATHS.len() <= max_count,
+            "texture count {} exceeds the device limit of {max_count}",
+            TEX_PATHS.len(),
+        );
         Self(ImageTextureArray::new(
             renderer,
             Self::tex_paths(),
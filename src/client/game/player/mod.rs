@@ -12,7 +12,7 @@ use crate::{
         event_loop::{Event, EventHandler},
         renderer::{Renderer, buffer::MemoryState, uniform::Uniform},
     },
-    server::game::world::chunk::Chunk,
+    server::{ServerEvent, game::world::chunk::Chunk},
     shared::color::Float3,
 };
 use bytemuck::{Pod, Zeroable};
@@ -77,11 +77,12 @@ impl EventHandler for Player {
         self.controller.handle(event, ());
 
         match event {
-            Event::Resumed => {
+            Event::UserEvent(ServerEvent::Connected) => {
                 _ = client_tx.send(ClientEvent::InitialRenderRequested {
                     origin: self.view.origin,
                     dir: self.view.forward,
                     render_distance: CLIENT_CONFIG.player.render_distance,
+                    name: CLIENT_CONFIG.player.name.clone(),
                 });
             }
             Event::WindowEvent {
@@ -106,12 +107,14 @@ impl EventHandler for Player {
                     self.projection.aspect = renderer.aspect();
                 }
 
-                if changes.contains(Changes::BLOCK_PLACED) {
-                    if let Some(block) = gui.selected_block() {
-                        _ = client_tx.send(ClientEvent::BlockPlaced(block));
+                if gui.is_connected() {
+                    if changes.contains(Changes::BLOCK_PLACED) {
+                        if let Some(block) = gui.selected_block() {
+                            _ = client_tx.send(ClientEvent::BlockPlaced(block));
+                        }
+                    } else if changes.contains(Changes::BLOCK_DESTROYED) {
+                        _ = client_tx.send(ClientEvent::BlockDestroyed);
                     }
-                } else if changes.contains(Changes::BLOCK_DESTROYED) {
-                    _ = client_tx.send(ClientEvent::BlockDestroyed);
                 }
 
                 if changes.intersects(Changes::VIEW) || renderer.is_resized {
@@ -168,6 +171,7 @@ impl PlayerUniformData {
 
 #[derive(Deserialize)]
 pub struct PlayerConfig {
+    name: String,
     origin: Point3<f32>,
     fovy: f32,
     speed: f32,
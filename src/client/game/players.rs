@@ -0,0 +1,436 @@
+use super::world::BlockVertex;
+use crate::{
+    client::{
+        CLIENT_CONFIG,
+        event_loop::{Event, EventHandler},
+        renderer::{
+            Renderer,
+            buffer::{Instance, InstanceBuffer, MemoryState, Vertex, VertexBuffer},
+            effect::PostProcessor,
+            program::{Program, PushConstants},
+            texture::{image::ImageTexture, screen::DepthBuffer},
+        },
+    },
+    server::{ClientId, ServerEvent, game::world::block::Block},
+    shared::color::Rgb,
+};
+use bytemuck::{Pod, Zeroable};
+use nalgebra::{Isometry3, Matrix4, Point2, Point3, UnitQuaternion, Vector3, point, vector};
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use std::time::Duration;
+use winit::event::WindowEvent;
+
+pub struct Players {
+    avatars: FxHashMap<ClientId, Avatar>,
+    body: AvatarBody,
+    label: AvatarLabel,
+}
+
+impl Players {
+    pub fn new(
+        renderer: &Renderer,
+        player_bind_group_layout: &wgpu::BindGroupLayout,
+        sky_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        Self {
+            avatars: Default::default(),
+            body: AvatarBody::new(renderer, player_bind_group_layout, sky_bind_group_layout),
+            label: AvatarLabel::new(renderer, player_bind_group_layout),
+        }
+    }
+
+    pub fn draw(
+        &self,
+        view: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+        player_bind_group: &wgpu::BindGroup,
+        sky_bind_group: &wgpu::BindGroup,
+        depth_view: &wgpu::TextureView,
+    ) {
+        if self.avatars.is_empty() {
+            return;
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            ..Default::default()
+        });
+
+        self.body.draw(
+            &mut render_pass,
+            player_bind_group,
+            sky_bind_group,
+            self.avatars.values(),
+        );
+
+        self.label
+            .draw(&mut render_pass, player_bind_group, self.avatars.values());
+    }
+}
+
+impl EventHandler for Players {
+    type Context<'a> = (&'a Renderer, Duration);
+
+    fn handle(&mut self, event: &Event, (renderer, dt): Self::Context<'_>) {
+        match *event {
+            Event::UserEvent(ServerEvent::PlayerJoined {
+                id,
+                ref name,
+                origin,
+                dir,
+            }) => {
+                self.avatars
+                    .insert(id, Avatar::new(renderer, name, Pose { origin, dir }));
+            }
+            Event::UserEvent(ServerEvent::PlayerMoved { id, origin, dir }) => {
+                if let Some(avatar) = self.avatars.get_mut(&id) {
+                    avatar.update(Pose { origin, dir });
+                }
+            }
+            Event::UserEvent(ServerEvent::PlayerLeft(id)) => {
+                self.avatars.remove(&id);
+            }
+            Event::UserEvent(ServerEvent::Disconnected) => {
+                self.avatars.clear();
+            }
+            Event::WindowEvent {
+                event: WindowEvent::RedrawRequested,
+                ..
+            } => {
+                for avatar in self.avatars.values_mut() {
+                    avatar.elapsed += dt;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Avatar {
+    label: Option<InstanceBuffer<GlyphInstance>>,
+    prev: Pose,
+    cur: Pose,
+    elapsed: Duration,
+}
+
+impl Avatar {
+    fn new(renderer: &Renderer, name: &str, pose: Pose) -> Self {
+        let len = name.chars().count() as f32;
+        Self {
+            label: InstanceBuffer::new_non_empty(
+                renderer,
+                MemoryState::Immutable(
+                    &name
+                        .chars()
+                        .enumerate()
+                        .map(|(i, c)| GlyphInstance::new(i as f32 - len / 2.0, c))
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+            prev: pose,
+            cur: pose,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    fn update(&mut self, pose: Pose) {
+        self.prev = self.pose();
+        self.cur = pose;
+        self.elapsed = Duration::ZERO;
+    }
+
+    fn pose(&self) -> Pose {
+        let t = (self.elapsed.as_secs_f32() / CLIENT_CONFIG.players.interpolation).min(1.0);
+        Pose {
+            origin: self.prev.origin.lerp(&self.cur.origin, t),
+            dir: self
+                .prev
+                .dir
+                .lerp(&self.cur.dir, t)
+                .try_normalize(f32::EPSILON)
+                .unwrap_or(self.cur.dir),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Pose {
+    origin: Point3<f32>,
+    dir: Vector3<f32>,
+}
+
+impl Pose {
+    fn body(self) -> Matrix4<f32> {
+        let config = &CLIENT_CONFIG.players;
+        Isometry3::from_parts(self.origin.into(), self.yaw()).to_homogeneous()
+            * Matrix4::new_translation(&vector![
+                -config.body.x / 2.0,
+                -config.eye_height,
+                -config.body.z / 2.0
+            ])
+            * Matrix4::new_nonuniform_scaling(&config.body)
+    }
+
+    fn head(self) -> Matrix4<f32> {
+        let head = CLIENT_CONFIG.players.head;
+        Isometry3::from_parts(self.origin.into(), self.yaw() * self.pitch()).to_homogeneous()
+            * Matrix4::new_translation(&Vector3::repeat(-head / 2.0))
+            * Matrix4::new_scaling(head)
+    }
+
+    fn label(self) -> Point3<f32> {
+        let config = &CLIENT_CONFIG.players;
+        self.origin + Vector3::y() * (config.head / 2.0 + config.label_gap)
+    }
+
+    fn yaw(self) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -self.dir.z.atan2(self.dir.x))
+    }
+
+    fn pitch(self) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_axis_angle(&Vector3::z_axis(), self.dir.y.clamp(-1.0, 1.0).asin())
+    }
+}
+
+struct AvatarBody {
+    vertex_buffer: VertexBuffer<BlockVertex>,
+    program: Program,
+}
+
+impl AvatarBody {
+    fn new(
+        renderer: &Renderer,
+        player_bind_group_layout: &wgpu::BindGroupLayout,
+        sky_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        Self {
+            vertex_buffer: VertexBuffer::new(renderer, MemoryState::Immutable(&Self::vertices())),
+            program: Program::new(
+                renderer,
+                wgpu::include_wgsl!("../../../assets/shaders/avatar.wgsl"),
+                &[BlockVertex::desc()],
+                &[player_bind_group_layout, sky_bind_group_layout],
+                &[AvatarPushConstants::range()],
+                Some(wgpu::Face::Back),
+                Some(wgpu::DepthStencilState {
+                    format: DepthBuffer::FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::LessEqual,
+                    stencil: Default::default(),
+                    bias: Default::default(),
+                }),
+                PostProcessor::FORMAT,
+                None,
+            ),
+        }
+    }
+
+    fn draw<'a, A: IntoIterator<Item = &'a Avatar>>(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        player_bind_group: &wgpu::BindGroup,
+        sky_bind_group: &wgpu::BindGroup,
+        avatars: A,
+    ) {
+        self.program
+            .bind(render_pass, [player_bind_group, sky_bind_group]);
+        for avatar in avatars {
+            let pose = avatar.pose();
+            for m in [pose.body(), pose.head()] {
+                AvatarPushConstants::new(m).set(render_pass);
+                self.vertex_buffer.draw(render_pass);
+            }
+        }
+    }
+
+    fn vertices() -> Vec<BlockVertex> {
+        Block::SAND
+            .data()
+            .mesh(Default::default(), Block::SAND.into(), &Default::default())
+            .collect()
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod)]
+struct AvatarPushConstants {
+    m: Matrix4<f32>,
+    color: u32,
+}
+
+impl AvatarPushConstants {
+    fn new(m: Matrix4<f32>) -> Self {
+        Self {
+            m,
+            color: CLIENT_CONFIG
+                .players
+                .color
+                .into_iter()
+                .rev()
+                .fold(0, |acc, c| {
+                    acc << 8 | (c.clamp(0.0, 1.0) * 255.0).round() as u32
+                }),
+        }
+    }
+}
+
+impl PushConstants for AvatarPushConstants {
+    const STAGES: wgpu::ShaderStages = wgpu::ShaderStages::VERTEX;
+}
+
+struct AvatarLabel {
+    vertex_buffer: VertexBuffer<LabelVertex>,
+    texture: ImageTexture,
+    program: Program,
+}
+
+impl AvatarLabel {
+    fn new(renderer: &Renderer, player_bind_group_layout: &wgpu::BindGroupLayout) -> Self {
+        let texture = ImageTexture::new(
+            renderer,
+            FONT_PATH,
+            1,
+            false,
+            wgpu::AddressMode::ClampToEdge,
+        );
+        Self {
+            vertex_buffer: VertexBuffer::new(
+                renderer,
+                MemoryState::Immutable(&CORNERS.map(LabelVertex::new)),
+            ),
+            program: Program::new(
+                renderer,
+                wgpu::include_wgsl!("../../../assets/shaders/label.wgsl"),
+                &[LabelVertex::desc(), GlyphInstance::desc()],
+                &[player_bind_group_layout, texture.bind_group_layout()],
+                &[LabelPushConstants::range()],
+                None,
+                Some(wgpu::DepthStencilState {
+                    format: DepthBuffer::FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::LessEqual,
+                    stencil: Default::default(),
+                    bias: Default::default(),
+                }),
+                PostProcessor::FORMAT,
+                None,
+            ),
+            texture,
+        }
+    }
+
+    fn draw<'a, A: IntoIterator<Item = &'a Avatar>>(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        player_bind_group: &wgpu::BindGroup,
+        avatars: A,
+    ) {
+        self.program
+            .bind(render_pass, [player_bind_group, self.texture.bind_group()]);
+        for avatar in avatars {
+            if let Some(label) = &avatar.label {
+                LabelPushConstants::new(avatar.pose().label()).set(render_pass);
+                self.vertex_buffer.draw_instanced(render_pass, label);
+            }
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod)]
+pub struct LabelVertex {
+    corner: Point2<f32>,
+}
+
+impl LabelVertex {
+    pub fn new(corner: Point2<f32>) -> Self {
+        Self { corner }
+    }
+}
+
+impl Vertex for LabelVertex {
+    const ATTRIBS: &[wgpu::VertexAttribute] = &wgpu::vertex_attr_array![0 => Float32x2];
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod)]
+pub struct GlyphInstance {
+    offset: f32,
+    index: u32,
+}
+
+impl GlyphInstance {
+    pub fn new(offset: f32, c: char) -> Self {
+        let c = if c == ' ' || c.is_ascii_graphic() {
+            c
+        } else {
+            '?'
+        };
+        Self {
+            offset,
+            index: c as u32 - ' ' as u32,
+        }
+    }
+}
+
+impl Instance for GlyphInstance {
+    const ATTRIBS: &[wgpu::VertexAttribute] = &wgpu::vertex_attr_array![1 => Float32, 2 => Uint32];
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Zeroable, Pod)]
+struct LabelPushConstants {
+    origin: Point3<f32>,
+    size: f32,
+}
+
+impl LabelPushConstants {
+    fn new(origin: Point3<f32>) -> Self {
+        Self {
+            origin,
+            size: CLIENT_CONFIG.players.label_size,
+        }
+    }
+}
+
+impl PushConstants for LabelPushConstants {
+    const STAGES: wgpu::ShaderStages = wgpu::ShaderStages::VERTEX;
+}
+
+#[derive(Deserialize)]
+pub struct PlayersConfig {
+    color: Rgb<f32>,
+    eye_height: f32,
+    body: Vector3<f32>,
+    head: f32,
+    label_size: f32,
+    label_gap: f32,
+    interpolation: f32,
+}
+
+pub const FONT_PATH: &str = "assets/textures/font.png";
+
+pub const CORNERS: [Point2<f32>; 6] = [
+    point![0.0, 0.0],
+    point![1.0, 0.0],
+    point![1.0, 1.0],
+    point![0.0, 0.0],
+    point![1.0, 1.0],
+    point![0.0, 1.0],
+];
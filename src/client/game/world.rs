@@ -16,14 +16,14 @@ use crate::{
         game::world::{
             ChunkData,
             block::{BlockLight, data::Face},
-            chunk::Chunk,
+            chunk::{Chunk, ChunkSnapshot, area::ChunkArea},
         },
     },
     shared::{pool::ThreadPool, utils},
 };
 use bitfield::{BitRange as _, BitRangeMut as _};
 use bytemuck::{Pod, Zeroable};
-use nalgebra::{Point2, Point3, point};
+use nalgebra::{Point2, Point3, Vector3, point};
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::{cmp::Reverse, collections::hash_map::Entry, sync::Arc, time::Instant};
 use uuid::Uuid;
@@ -31,14 +31,20 @@ use winit::event::WindowEvent;
 
 pub struct World {
     meshes: FxHashMap<Point3<i32>, (ChunkMesh, Instant)>,
+    mirror: FxHashMap<Point3<i32>, Arc<ChunkSnapshot>>,
     program: Program,
     unloaded: FxHashSet<Point3<i32>>,
+    cleared_at: Instant,
     groups: FxHashMap<Uuid, Vec<Result<ChunkOutput, Point3<i32>>>>,
     group_workers: ThreadPool<(ChunkInput, GroupId), (ChunkOutput, GroupId)>,
     workers: ThreadPool<ChunkInput, ChunkOutput>,
 }
 
-type ChunkInput = (Point3<i32>, Arc<ChunkData>, Instant);
+type ChunkInput = (
+    Point3<i32>,
+    Vec<(Vector3<i32>, Arc<ChunkSnapshot>)>,
+    Instant,
+);
 
 type ChunkOutput = (Point3<i32>, (Vec<BlockVertex>, Vec<BlockVertex>), Instant);
 
@@ -51,6 +57,7 @@ impl World {
     ) -> Self {
         Self {
             meshes: Default::default(),
+            mirror: Default::default(),
             program: Program::new(
                 renderer,
                 wgpu::include_wgsl!("../../../assets/shaders/block.wgsl"),
@@ -73,6 +80,7 @@ impl World {
                 Some(wgpu::BlendState::ALPHA_BLENDING),
             ),
             unloaded: Default::default(),
+            cleared_at: Instant::now(),
             groups: Default::default(),
             group_workers: ThreadPool::new(|(input, group_id)| (Self::vertices(input), group_id)),
             workers: ThreadPool::new(Self::vertices),
@@ -141,6 +149,21 @@ impl World {
         }
     }
 
+    fn clear(&mut self) {
+        self.meshes.clear();
+        self.mirror.clear();
+        self.unloaded.clear();
+        self.groups.clear();
+        self.cleared_at = Instant::now();
+    }
+
+    fn input(&self, coords: Point3<i32>) -> ChunkInput {
+        let snapshots = ChunkArea::chunk_deltas()
+            .filter_map(|delta| Some((delta, self.mirror.get(&(coords + delta))?.clone())))
+            .collect();
+        (coords, snapshots, Instant::now())
+    }
+
     fn send(&self, input: ChunkInput, group_id: Option<GroupId>) {
         if let Some(group_id) = group_id {
             self.group_workers
@@ -157,6 +180,12 @@ impl World {
         output: Result<ChunkOutput, Point3<i32>>,
         group_id: Option<GroupId>,
     ) {
+        if let Ok((_, _, updated_at)) = output
+            && updated_at < self.cleared_at
+        {
+            return;
+        }
+
         let Some(GroupId {
             id: group_id,
             size: group_size,
@@ -219,7 +248,12 @@ impl World {
         }
     }
 
-    fn vertices((coords, data, updated_at): ChunkInput) -> ChunkOutput {
+    fn vertices((coords, snapshots, updated_at): ChunkInput) -> ChunkOutput {
+        let data = ChunkData::new(
+            snapshots
+                .iter()
+                .map(|(delta, snapshot)| (*delta, &**snapshot)),
+        );
         (coords, data.vertices(), updated_at)
     }
 
@@ -261,25 +295,31 @@ impl EventHandler for World {
     fn handle(&mut self, event: &Event, renderer: Self::Context<'_>) {
         match event {
             Event::UserEvent(event) => match event {
-                ServerEvent::ChunkLoaded {
-                    coords,
-                    data,
-                    group_id,
-                } => {
+                ServerEvent::ChunkMirrored { coords, snapshot } => {
+                    self.mirror.insert(*coords, snapshot.clone());
+                }
+                ServerEvent::ChunkUnmirrored { coords } => {
+                    self.mirror.remove(coords);
+                }
+                ServerEvent::BlocksChanged { deltas } => {
+                    for &(coords, block, light) in deltas {
+                        if let Some(snapshot) = self.mirror.get_mut(&utils::chunk_coords(coords)) {
+                            Arc::make_mut(snapshot).set(utils::block_coords(coords), block, light);
+                        }
+                    }
+                }
+                ServerEvent::ChunkLoaded { coords, group_id } => {
                     self.unloaded.remove(coords);
-                    self.send((*coords, data.clone(), Instant::now()), *group_id);
+                    self.send(self.input(*coords), *group_id);
                 }
                 ServerEvent::ChunkUnloaded { coords, group_id } => {
                     self.unloaded.insert(*coords);
                     self.process_output(renderer, Err(*coords), *group_id);
                 }
-                ServerEvent::ChunkUpdated {
-                    coords,
-                    data,
-                    group_id,
-                } => {
-                    self.send((*coords, data.clone(), Instant::now()), *group_id);
+                ServerEvent::ChunkUpdated { coords, group_id } => {
+                    self.send(self.input(*coords), *group_id);
                 }
+                ServerEvent::Disconnected => self.clear(),
                 _ => {}
             },
             Event::WindowEvent {
@@ -323,7 +363,6 @@ impl ChunkMesh {
             TransparentPart::new_non_empty(renderer, transparent_vertices, |v| {
                 v.iter()
                     .fold(Point3::default(), |acc, v| acc + v.coords().coords)
-                    .cast()
                     / v.len() as f32
             }),
         ) {
@@ -358,42 +397,46 @@ impl ChunkMesh {
 #[repr(C)]
 #[derive(Clone, Copy, Zeroable, Pod)]
 pub struct BlockVertex {
-    data: [u32; 2],
+    data: [u32; 3],
 }
 
 impl BlockVertex {
+    pub const MAX_TEX_COUNT: usize = 1 << 11;
+    pub const RESOLUTION: u8 = 16;
+
     pub fn new(
-        coords: Point3<u8>,
-        tex_index: u8,
-        tex_coords: Point2<u8>,
+        coords: Point3<u16>,
+        tex_index: u16,
+        tex_coords: Point2<u16>,
         face: Face,
         ao: u8,
         light: BlockLight,
     ) -> Self {
-        let mut data = [0; 2];
-        data[0].set_bit_range(4, 0, coords.x);
-        data[0].set_bit_range(9, 5, coords.y);
-        data[0].set_bit_range(14, 10, coords.z);
-        data[0].set_bit_range(22, 15, tex_index);
-        data[0].set_bit_range(31, 27, tex_coords.x);
-        data[1].set_bit_range(31, 27, tex_coords.y);
-        data[0].set_bit_range(24, 23, face as u8);
-        data[0].set_bit_range(26, 25, ao);
-        data[1].set_bit_range(26, 0, light.0);
+        let mut data = [0; 3];
+        data[0].set_bit_range(8, 0, coords.x);
+        data[0].set_bit_range(17, 9, coords.y);
+        data[0].set_bit_range(26, 18, coords.z);
+        data[0].set_bit_range(28, 27, face as u8);
+        data[0].set_bit_range(30, 29, ao);
+        data[1].set_bit_range(23, 0, light.0);
+        data[2].set_bit_range(8, 0, tex_coords.x);
+        data[2].set_bit_range(17, 9, tex_coords.y);
+        data[2].set_bit_range(28, 18, tex_index);
         Self { data }
     }
 
-    fn coords(self) -> Point3<u8> {
-        point![
-            self.data[0].bit_range(4, 0),
-            self.data[0].bit_range(9, 5),
-            self.data[0].bit_range(14, 10),
-        ]
+    fn coords(self) -> Point3<f32> {
+        let coords: Point3<u16> = point![
+            self.data[0].bit_range(8, 0),
+            self.data[0].bit_range(17, 9),
+            self.data[0].bit_range(26, 18),
+        ];
+        coords.cast() / Self::RESOLUTION as f32
     }
 }
 
 impl Vertex for BlockVertex {
-    const ATTRIBS: &[wgpu::VertexAttribute] = &wgpu::vertex_attr_array![0 => Uint32x2];
+    const ATTRIBS: &[wgpu::VertexAttribute] = &wgpu::vertex_attr_array![0 => Uint32x3];
 }
 
 #[repr(C)]
@@ -7,10 +7,16 @@ pub(crate) mod window;
 
 use self::{
     event_loop::{EventLoop, EventLoopProxy},
-    game::{cloud::CloudConfig, gui::GuiConfig, player::PlayerConfig, sky::SkyConfig},
+    game::{
+        cloud::CloudConfig, gui::GuiConfig, player::PlayerConfig, players::PlayersConfig,
+        sky::SkyConfig,
+    },
 };
 use crate::{
-    server::{ServerSender, game::world::block::Block},
+    server::{
+        ServerSender,
+        game::world::block::{Block, BlockRemap},
+    },
     shared::utils,
 };
 use app::App;
@@ -59,6 +65,7 @@ pub enum ClientEvent {
         origin: Point3<f32>,
         dir: Vector3<f32>,
         render_distance: u32,
+        name: String,
     },
     PlayerPositionChanged {
         origin: Point3<f32>,
@@ -68,19 +75,55 @@ pub enum ClientEvent {
     },
     BlockPlaced(Block),
     BlockDestroyed,
+    Pong,
     #[serde(skip)]
     Connected(Box<ServerSender>),
     #[serde(skip)]
+    Disconnected,
+    #[serde(skip)]
     ServerDisconnected,
 }
 
+impl ClientEvent {
+    pub fn remap_blocks(self, remap: &BlockRemap) -> Option<Self> {
+        match self {
+            Self::BlockPlaced(block) => remap.to_remote(block).map(Self::BlockPlaced),
+            event => Some(event),
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct ClientConfig {
     player: PlayerConfig,
     sky: SkyConfig,
     cloud: CloudConfig,
+    players: PlayersConfig,
     gui: GuiConfig,
 }
 
 static CLIENT_CONFIG: LazyLock<ClientConfig> =
     LazyLock::new(|| utils::deserialize("assets/config/client.toml"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::game::world::block::data::{BLOCK_NAMES, STR_TO_BLOCK};
+
+    #[test]
+    fn remap_drops_unmappable_placements() {
+        let mut remote = BLOCK_NAMES.to_vec();
+        remote.retain(|str| &**str != "stone");
+        let remap = BlockRemap::new(&remote).expect("changed registry should need a remap");
+
+        assert!(
+            ClientEvent::BlockPlaced(STR_TO_BLOCK["stone"])
+                .remap_blocks(&remap)
+                .is_none()
+        );
+        assert!(matches!(
+            ClientEvent::BlockDestroyed.remap_blocks(&remap),
+            Some(ClientEvent::BlockDestroyed),
+        ));
+    }
+}
@@ -88,6 +88,14 @@ impl<E: Pod> InstanceBuffer<E> {
             state.usage(wgpu::BufferUsages::VERTEX),
         ))
     }
+
+    pub fn new_non_empty(renderer: &Renderer, state: MemoryState<[E], usize>) -> Option<Self> {
+        Some(Self(Buffer::<[_]>::new_non_empty(
+            renderer,
+            state.data(),
+            state.usage(wgpu::BufferUsages::VERTEX),
+        )?))
+    }
 }
 
 impl<E> Deref for InstanceBuffer<E> {
@@ -44,6 +44,9 @@ impl Renderer {
                         | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
                     required_limits: wgpu::Limits {
                         max_push_constant_size: 68,
+                        max_sampled_textures_per_shader_stage: adapter
+                            .limits()
+                            .max_sampled_textures_per_shader_stage,
                         ..Default::default()
                     },
                     ..Default::default()
@@ -1,4 +1,5 @@
 use super::event_loop::{Event, EventHandler};
+use crate::server::ServerEvent;
 use std::{ops::Deref, sync::Arc};
 use winit::{
     error::ExternalError,
@@ -65,6 +66,10 @@ impl EventHandler for Window {
                 }
                 _ => {}
             },
+            Event::UserEvent(ServerEvent::Disconnected) => {
+                self.0.set_title("Crustcrab (disconnected)");
+            }
+            Event::UserEvent(ServerEvent::Connected) => self.0.set_title("Crustcrab"),
             Event::AboutToWait => self.0.request_redraw(),
             _ => {}
         }
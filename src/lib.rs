@@ -5,7 +5,6 @@
     impl_trait_in_assoc_type,
     int_roundings,
     let_chains,
-    maybe_uninit_fill,
     trait_alias,
     type_alias_impl_trait
 )]
@@ -0,0 +1,247 @@
+use flate2::{read::DeflateDecoder, write::DeflateEncoder};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+const MAX_FRAME_LEN: u32 = 1 << 26;
+
+pub struct FrameWriter<W> {
+    writer: W,
+    stats: CompressionStats,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            stats: Default::default(),
+        }
+    }
+
+    pub fn write<T: Serialize>(&mut self, value: &T, compression: Compression) -> io::Result<()> {
+        let bytes = compression
+            .encode(value, &mut self.stats)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let len = u32::try_from(bytes.len())
+            .ok()
+            .filter(|&len| len <= MAX_FRAME_LEN)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "frame of {} bytes exceeds {MAX_FRAME_LEN} bytes",
+                        bytes.len()
+                    ),
+                )
+            })?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&bytes)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn stats(&self) -> CompressionStats {
+        self.stats
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub struct FrameReader<R> {
+    reader: R,
+    stats: CompressionStats,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            stats: Default::default(),
+        }
+    }
+
+    pub fn read<T: DeserializeOwned>(&mut self) -> io::Result<bincode::Result<T>> {
+        let bytes = self.read_frame()?;
+        Ok(self.decode(&bytes))
+    }
+
+    pub fn read_frame(&mut self) -> io::Result<Vec<u8>> {
+        let mut len = [0; 4];
+        self.reader.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len);
+        if len > MAX_FRAME_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("frame of {len} bytes exceeds {MAX_FRAME_LEN} bytes"),
+            ));
+        }
+        let mut bytes = vec![0; len as usize];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn decode<T: DeserializeOwned>(&mut self, bytes: &[u8]) -> bincode::Result<T> {
+        Compression::decode(bytes, &mut self.stats)
+    }
+
+    pub fn stats(&self) -> CompressionStats {
+        self.stats
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Compression {
+    None,
+    Deflate,
+}
+
+impl Compression {
+    pub fn negotiate(self, peer: Self) -> Self {
+        if self == peer { self } else { Self::None }
+    }
+
+    fn encode<T: Serialize>(
+        self,
+        value: &T,
+        stats: &mut CompressionStats,
+    ) -> bincode::Result<Vec<u8>> {
+        match self {
+            Self::None => bincode::serialize(&Payload::Plain(value)),
+            Self::Deflate => {
+                let mut encoder = DeflateEncoder::new(vec![], flate2::Compression::fast());
+                bincode::serialize_into(&mut encoder, value)?;
+                stats.raw += encoder.total_in();
+                let bytes = encoder.finish()?;
+                stats.compressed += bytes.len() as u64;
+                bincode::serialize(&Payload::<&T>::Deflated(bytes))
+            }
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(
+        bytes: &[u8],
+        stats: &mut CompressionStats,
+    ) -> bincode::Result<T> {
+        match bincode::deserialize(bytes)? {
+            Payload::Plain(value) => Ok(value),
+            Payload::Deflated(bytes) => {
+                let mut decoder = DeflateDecoder::new(&*bytes);
+                let value = bincode::deserialize_from(&mut decoder)?;
+                stats.raw += decoder.total_out();
+                stats.compressed += bytes.len() as u64;
+                Ok(value)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum Payload<T> {
+    Plain(T),
+    Deflated(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct CompressionStats {
+    pub raw: u64,
+    pub compressed: u64,
+}
+
+impl fmt::Display for CompressionStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes compressed into {} bytes ({:.1}%)",
+            self.raw,
+            self.compressed,
+            self.compressed as f64 / self.raw.max(1) as f64 * 100.0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(values: &[Vec<u32>], compression: Compression) -> Vec<Vec<u32>> {
+        let mut writer = FrameWriter::new(vec![]);
+        for value in values {
+            writer.write(value, compression).unwrap();
+        }
+        let bytes = writer.writer;
+        let mut reader = FrameReader::new(&*bytes);
+        let values = values
+            .iter()
+            .map(|_| reader.read().unwrap().unwrap())
+            .collect();
+        assert_eq!(
+            reader.read::<Vec<u32>>().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        values
+    }
+
+    #[test]
+    fn frames_round_trip_uncompressed() {
+        let values = [vec![], vec![1, 2, 3], vec![u32::MAX; 100]];
+        assert_eq!(round_trip(&values, Compression::None), values);
+    }
+
+    #[test]
+    fn frames_round_trip_deflated() {
+        let values = [vec![], vec![1, 2, 3], vec![7; 10000]];
+        assert_eq!(round_trip(&values, Compression::Deflate), values);
+    }
+
+    #[test]
+    fn deflate_shrinks_repetitive_frames() {
+        let mut writer = FrameWriter::new(vec![]);
+        writer
+            .write(&vec![7u32; 10000], Compression::Deflate)
+            .unwrap();
+        assert!(writer.writer.len() < 1000);
+        assert!(writer.stats().compressed < writer.stats().raw);
+    }
+
+    #[test]
+    fn read_rejects_oversized_frame() {
+        let bytes = (MAX_FRAME_LEN + 1).to_le_bytes();
+        let mut reader = FrameReader::new(&bytes[..]);
+        assert_eq!(
+            reader.read::<u32>().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn read_reports_truncated_frame() {
+        let mut writer = FrameWriter::new(vec![]);
+        writer.write(&vec![1u32; 10], Compression::None).unwrap();
+        let bytes = &writer.writer[..writer.writer.len() - 1];
+        assert_eq!(
+            FrameReader::new(bytes)
+                .read::<Vec<u32>>()
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn negotiate_falls_back_to_none() {
+        assert_eq!(
+            Compression::Deflate.negotiate(Compression::Deflate),
+            Compression::Deflate
+        );
+        assert_eq!(
+            Compression::Deflate.negotiate(Compression::None),
+            Compression::None
+        );
+    }
+}
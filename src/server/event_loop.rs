@@ -1,40 +1,43 @@
-use super::{SERVER_CONFIG, ServerSender, ticker::Ticker};
+use super::{ClientId, Clients, SERVER_CONFIG, ticker::Ticker};
 use crate::client::ClientEvent;
 use crossbeam_channel::{Receiver, RecvTimeoutError};
 use serde::Deserialize;
 
 pub struct EventLoop {
-    server_tx: ServerSender,
-    client_rx: Receiver<ClientEvent>,
+    clients: Clients,
+    client_rx: Receiver<(ClientId, ClientEvent)>,
 }
 
 impl EventLoop {
-    pub fn new(server_tx: ServerSender, client_rx: Receiver<ClientEvent>) -> Self {
+    pub fn new(client_rx: Receiver<(ClientId, ClientEvent)>) -> Self {
         Self {
-            server_tx,
+            clients: Default::default(),
             client_rx,
         }
     }
 
     pub fn run<H>(&mut self, mut handler: H)
     where
-        H: for<'a> EventHandler<Event, Context<'a> = &'a ServerSender>,
+        H: for<'a> EventHandler<Event, Context<'a> = &'a Clients>,
     {
         let mut ticker = Ticker::start(SERVER_CONFIG.event_loop.ticks_per_second);
-        handler.handle(&Event::Init, &self.server_tx);
+        handler.handle(&Event::Init, &self.clients);
         loop {
-            handler.handle(
-                &match ticker.recv_timeout(&self.client_rx) {
-                    Ok(ClientEvent::Connected(server_tx)) => {
-                        self.server_tx = *server_tx;
-                        continue;
-                    }
-                    Ok(event) => Event::Client(event),
-                    Err(RecvTimeoutError::Timeout) => Event::Tick,
-                    Err(RecvTimeoutError::Disconnected) => break,
-                },
-                &self.server_tx,
-            );
+            let event = match ticker.recv_timeout(&self.client_rx) {
+                Ok((id, ClientEvent::Connected(server_tx))) => {
+                    self.clients.insert(id, *server_tx);
+                    continue;
+                }
+                Ok((id, event)) => Event::Client(id, event),
+                Err(RecvTimeoutError::Timeout) => Event::Tick,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            handler.handle(&event, &self.clients);
+
+            if let Event::Client(id, ClientEvent::Disconnected) = event {
+                self.clients.remove(id);
+            }
         }
     }
 }
@@ -47,7 +50,7 @@ pub trait EventHandler<E> {
 
 pub enum Event {
     Init,
-    Client(ClientEvent),
+    Client(ClientId, ClientEvent),
     Tick,
 }
 
@@ -1,7 +1,7 @@
 use crate::{
     client::ClientEvent,
     server::{
-        SERVER_CONFIG, ServerEvent, ServerSender,
+        Clients, SERVER_CONFIG, ServerEvent,
         event_loop::{Event, EventHandler},
     },
     shared::utils::{self, Lerp},
@@ -16,10 +16,6 @@ pub struct Clock {
 }
 
 impl Clock {
-    fn send(self, server_tx: &ServerSender) {
-        _ = server_tx.send(ServerEvent::TimeUpdated(self.time()));
-    }
-
     fn time(self) -> Time {
         Time { ticks: self.ticks }
     }
@@ -34,16 +30,16 @@ impl Default for Clock {
 }
 
 impl EventHandler<Event> for Clock {
-    type Context<'a> = &'a ServerSender;
+    type Context<'a> = &'a Clients;
 
-    fn handle(&mut self, event: &Event, server_tx: Self::Context<'_>) {
-        match event {
-            Event::Client(ClientEvent::InitialRenderRequested { .. }) => {
-                self.send(server_tx);
+    fn handle(&mut self, event: &Event, clients: Self::Context<'_>) {
+        match *event {
+            Event::Client(id, ClientEvent::InitialRenderRequested { .. }) => {
+                clients.send(id, ServerEvent::TimeUpdated(self.time()));
             }
             Event::Tick => {
                 self.ticks = (self.ticks + 1) % SERVER_CONFIG.clock.ticks_per_day;
-                self.send(server_tx);
+                clients.broadcast(|| ServerEvent::TimeUpdated(self.time()));
             }
             _ => {}
         }
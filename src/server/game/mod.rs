@@ -4,37 +4,41 @@ pub mod world;
 
 use self::{
     clock::Clock,
-    player::Player,
+    player::Players,
     world::{World, WorldEvent},
 };
 use super::{
-    ServerSender,
+    ClientId, Clients,
     event_loop::{Event, EventHandler},
 };
+use crate::client::ClientEvent;
 use crossbeam_channel::Sender;
 use std::thread;
 
 pub struct Game {
-    player: Player,
+    players: Players,
     clock: Clock,
-    world_tx: Sender<(WorldEvent, ServerSender)>,
+    world_tx: Sender<WorldInput>,
 }
 
-impl Default for Game {
-    fn default() -> Self {
-        let player = Default::default();
+impl Game {
+    pub fn new(seed: Option<u32>) -> Self {
+        let players = Default::default();
         let clock = Default::default();
         let (world_tx, world_rx) = crossbeam_channel::unbounded();
 
         thread::spawn(move || {
-            let mut world = World::default();
-            for (event, server_tx) in world_rx {
-                world.handle(&event, &server_tx);
+            let mut world = World::new(seed);
+            for input in world_rx {
+                match input {
+                    WorldInput::Event(id, event) => world.handle(&event, id),
+                    WorldInput::Tick => world.tick(),
+                }
             }
         });
 
         Self {
-            player,
+            players,
             clock,
             world_tx,
         }
@@ -42,16 +46,36 @@ impl Default for Game {
 }
 
 impl EventHandler<Event> for Game {
-    type Context<'a> = &'a ServerSender;
+    type Context<'a> = &'a Clients;
 
-    fn handle(&mut self, event: &Event, server_tx: Self::Context<'_>) {
-        self.player.handle(event, ());
-        self.clock.handle(event, server_tx);
+    fn handle(&mut self, event: &Event, clients: Self::Context<'_>) {
+        self.clock.handle(event, clients);
+        self.players.handle(event, clients);
 
-        if let Some(event) = WorldEvent::new(event, &self.player) {
+        if let Event::Tick = event {
             self.world_tx
-                .send((event, server_tx.clone()))
+                .send(WorldInput::Tick)
+                .unwrap_or_else(|_| unreachable!());
+        }
+
+        if let Event::Client(id, ref event) = *event
+            && let Some(event) = match event {
+                ClientEvent::Disconnected => Some(WorldEvent::ClientDisconnected),
+                _ => self
+                    .players
+                    .get(id)
+                    .zip(clients.get(id))
+                    .and_then(|(player, server_tx)| WorldEvent::new(event, player, server_tx)),
+            }
+        {
+            self.world_tx
+                .send(WorldInput::Event(id, event))
                 .unwrap_or_else(|_| unreachable!());
         }
     }
 }
+
+enum WorldInput {
+    Event(ClientId, WorldEvent),
+    Tick,
+}
@@ -1,49 +1,120 @@
 use super::world::World;
 use crate::{
     client::ClientEvent,
-    server::event_loop::{Event, EventHandler},
+    server::{
+        ClientId, Clients, SERVER_CONFIG, ServerEvent,
+        event_loop::{Event, EventHandler},
+    },
     shared::{ray::Ray, utils},
 };
 use nalgebra::{Point2, Point3, point};
 use rayon::prelude::*;
+use rustc_hash::FxHashMap;
 use serde::Deserialize;
 use std::ops::Range;
 
+#[derive(Default)]
+pub struct Players(FxHashMap<ClientId, Player>);
+
+impl Players {
+    pub fn get(&self, id: ClientId) -> Option<&Player> {
+        self.0.get(&id)
+    }
+
+    fn joined(id: ClientId, player: &Player) -> ServerEvent {
+        ServerEvent::PlayerJoined {
+            id,
+            name: player.name.clone(),
+            origin: player.ray.origin,
+            dir: player.ray.dir,
+        }
+    }
+}
+
+impl EventHandler<Event> for Players {
+    type Context<'a> = &'a Clients;
+
+    fn handle(&mut self, event: &Event, clients: Self::Context<'_>) {
+        match *event {
+            Event::Client(id, ref event @ ClientEvent::InitialRenderRequested { .. }) => {
+                let player = self.0.entry(id).or_default();
+                player.handle(event, ());
+
+                clients.broadcast_except(id, || Self::joined(id, player));
+
+                for (&other, player) in &self.0 {
+                    if other != id {
+                        clients.send(id, Self::joined(other, player));
+                    }
+                }
+            }
+            Event::Client(id, ClientEvent::Disconnected) => {
+                if self.0.remove(&id).is_some() {
+                    clients.broadcast_except(id, || ServerEvent::PlayerLeft(id));
+                }
+            }
+            Event::Client(id, ref event) => {
+                if let Some(player) = self.0.get_mut(&id) {
+                    player.handle(event, ());
+                }
+            }
+            Event::Tick => {
+                for (&id, player) in &mut self.0 {
+                    if player.is_moved {
+                        player.is_moved = false;
+                        clients.broadcast_except(id, || ServerEvent::PlayerMoved {
+                            id,
+                            origin: player.ray.origin,
+                            dir: player.ray.dir,
+                        });
+                    }
+                }
+            }
+            Event::Init => {}
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Player {
     pub prev: WorldArea,
     pub cur: WorldArea,
     pub ray: Ray,
+    name: String,
+    is_moved: bool,
 }
 
-impl EventHandler<Event> for Player {
+impl EventHandler<ClientEvent> for Player {
     type Context<'a> = ();
 
-    fn handle(&mut self, event: &Event, (): Self::Context<'_>) {
+    fn handle(&mut self, event: &ClientEvent, (): Self::Context<'_>) {
         self.prev = self.cur;
 
-        if let Event::Client(event) = event {
-            match *event {
-                ClientEvent::InitialRenderRequested {
-                    origin,
-                    dir,
-                    render_distance,
-                } => {
-                    self.cur = WorldArea {
-                        center: utils::chunk_coords(origin),
-                        radius: render_distance as i32,
-                    };
-                    self.ray = Ray { origin, dir };
-                }
-                ClientEvent::PlayerOrientationChanged { dir } => {
-                    self.ray.dir = dir;
-                }
-                ClientEvent::PlayerPositionChanged { origin } => {
-                    self.cur.center = utils::chunk_coords(origin);
-                    self.ray.origin = origin;
-                }
-                _ => {}
+        match *event {
+            ClientEvent::InitialRenderRequested {
+                origin,
+                dir,
+                render_distance,
+                ref name,
+            } => {
+                let config = &SERVER_CONFIG.player;
+                self.cur = WorldArea {
+                    center: utils::chunk_coords(origin),
+                    radius: render_distance.min(config.max_render_distance) as i32,
+                };
+                self.ray = Ray { origin, dir };
+                self.name = name.chars().take(config.max_name_len).collect();
             }
+            ClientEvent::PlayerOrientationChanged { dir } => {
+                self.ray.dir = dir;
+                self.is_moved = true;
+            }
+            ClientEvent::PlayerPositionChanged { origin } => {
+                self.cur.center = utils::chunk_coords(origin);
+                self.ray.origin = origin;
+                self.is_moved = true;
+            }
+            _ => {}
         }
     }
 }
@@ -78,7 +149,14 @@ impl WorldArea {
             .filter(move |&coords| !other.client_contains(coords))
     }
 
-    fn server_contains(self, coords: Point3<i32>) -> bool {
+    pub fn padded(self, padding: i32) -> Self {
+        Self {
+            radius: self.radius + padding,
+            ..self
+        }
+    }
+
+    pub fn server_contains(self, coords: Point3<i32>) -> bool {
         self.contains_xz(coords.xz())
     }
 
@@ -122,4 +200,29 @@ impl WorldArea {
 #[derive(Deserialize)]
 pub struct PlayerConfig {
     pub reach: Range<f32>,
+    max_render_distance: u32,
+    max_name_len: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn initial_render_is_bounded() {
+        let config = &SERVER_CONFIG.player;
+        let mut player = Player::default();
+        player.handle(
+            &ClientEvent::InitialRenderRequested {
+                origin: Point3::origin(),
+                dir: Vector3::x(),
+                render_distance: u32::MAX,
+                name: "x".repeat(config.max_name_len + 1),
+            },
+            (),
+        );
+        assert_eq!(player.cur.radius, config.max_render_distance as i32);
+        assert_eq!(player.name.chars().count(), config.max_name_len);
+    }
 }
@@ -1,6 +1,6 @@
 use super::block::Block;
 use crate::shared::utils;
-use nalgebra::Point3;
+use nalgebra::{Point3, Vector3};
 use rustc_hash::FxHashMap;
 
 #[derive(Default)]
@@ -21,6 +21,10 @@ impl ActionStore {
             .or_default()
             .insert(utils::block_coords(coords), action);
     }
+
+    pub fn remove(&mut self, coords: Point3<i32>) {
+        self.0.remove(&coords);
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -28,3 +32,12 @@ pub enum BlockAction {
     Place(Block),
     Destroy,
 }
+
+impl BlockAction {
+    pub fn oriented(self, normal: Vector3<i64>, dir: Vector3<f32>) -> Self {
+        match self {
+            Self::Place(block) => Self::Place(block.oriented(normal, dir)),
+            Self::Destroy => Self::Destroy,
+        }
+    }
+}
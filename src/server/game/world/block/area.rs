@@ -2,7 +2,7 @@ use super::{
     Block, BlockLight,
     data::{Component, Corner, SIDE_CORNER_COMPONENT_DELTAS, SIDE_DELTAS, Side},
 };
-use crate::{enum_map, shared::enum_map::EnumMap};
+use crate::{client::game::world::BlockVertex, enum_map, shared::enum_map::EnumMap};
 use nalgebra::{Vector3, vector};
 use std::{
     array,
@@ -25,8 +25,10 @@ impl BlockArea {
 
     pub fn is_side_visible(self, side: Option<Side>) -> bool {
         side.is_none_or(|side| {
+            let block = self.block();
             let neighbor = self[SIDE_DELTAS[side]];
-            neighbor != self.block() && neighbor.data().is_transparent()
+            let is_occluding = neighbor == block || !neighbor.data().requires_blending;
+            !is_occluding || !block.data().is_side_covered(side, neighbor.data())
         })
     }
 
@@ -38,6 +40,15 @@ impl BlockArea {
         }
     }
 
+    pub fn fluid_height(self, delta: Vector3<i8>) -> u8 {
+        let block = self[delta];
+        match block.data().fluid {
+            Some(_) if self[delta + Vector3::y()].base() == block.base() => BlockVertex::RESOLUTION,
+            Some(fluid) if block.base() == self.block().base() => fluid.height(),
+            _ => 0,
+        }
+    }
+
     pub fn block(self) -> Block {
         self[Default::default()]
     }
@@ -63,7 +74,7 @@ impl BlockArea {
     }
 
     fn components(self, side: Side, corner: Corner) -> EnumMap<Component, bool> {
-        SIDE_CORNER_COMPONENT_DELTAS[side][corner].map(|_, delta| self[delta].data().is_opaque())
+        SIDE_CORNER_COMPONENT_DELTAS[side][corner].map(|_, delta| self[delta].data().is_solid())
     }
 
     pub fn deltas() -> impl Iterator<Item = Vector3<i8>> {
@@ -2,6 +2,7 @@ use super::{
     Block, BlockLight,
     area::{BlockArea, BlockAreaLight},
     model::{Model, RawModel},
+    state::BlockKind,
 };
 use crate::{
     client::game::world::BlockVertex,
@@ -12,14 +13,16 @@ use crate::{
         color::Rgb,
         enum_map::{Enum, EnumMap},
         indexmap::FxIndexSet,
-        utils,
+        utils::{self, StableHasher},
     },
 };
-use nalgebra::{Point2, Point3, Vector3, point};
+use nalgebra::{Point2, Point3, Vector3, point, vector};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Deserializer};
 use std::{
-    array, iter,
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    iter,
     ops::Deref,
     sync::{Arc, LazyLock},
 };
@@ -31,9 +34,38 @@ pub struct BlockData {
     pub light_filter: Rgb<bool>,
     pub requires_blending: bool,
     pub valid_surface: Option<Block>,
+    pub fluid: Option<Fluid>,
+    kind: u16,
+    pub state: u16,
 }
 
 impl BlockData {
+    fn new(data: &RawBlockData, index: usize, kind: &BlockKind, state: usize) -> Self {
+        Self {
+            model: Model::new(&data.model, kind.rotation(state)),
+            luminance: data.luminance,
+            light_filter: data.light_filter,
+            requires_blending: data.requires_blending,
+            valid_surface: data.valid_surface.as_deref().map(|str| STR_TO_BLOCK[str]),
+            fluid: data.fluid.map(|fluid| {
+                let (Some(level), Some(levels)) = (kind.level(state), kind.levels()) else {
+                    panic!("fluid block \"{}\" must have a level property", kind.name());
+                };
+                Fluid {
+                    tick_delay: fluid.tick_delay,
+                    level,
+                    levels,
+                }
+            }),
+            kind: index as u16,
+            state: state as u16,
+        }
+    }
+
+    pub fn kind(self) -> &'static BlockKind {
+        &BLOCK_KINDS[self.kind as usize]
+    }
+
     pub fn vertices(
         self,
         side: Option<Side>,
@@ -43,16 +75,23 @@ impl BlockData {
         corner_aos: EnumMap<Corner, u8>,
         corner_lights: EnumMap<Corner, BlockLight>,
     ) -> impl Iterator<Item = BlockVertex> {
-        let corner_deltas = self.model.corner_deltas(side);
+        let tex_index = self.model.tex_index(side);
         let corners = Self::corners(corner_aos, corner_lights);
-        let face = side.into();
-        corner_deltas.iter().flat_map(move |corner_deltas| {
+        let resolution = BlockVertex::RESOLUTION as u16;
+        self.model.quads(side).iter().flat_map(move |quad| {
+            let face = quad.facing.into();
             corners.into_iter().map(move |corner| {
-                let tex_coords = CORNER_TEX_COORDS[corner];
                 BlockVertex::new(
-                    coords + corner_deltas[corner].component_mul(&dims.coords),
-                    self.model.tex_index,
-                    array::from_fn(|i| tex_coords[i] * tex_dims[i]).into(),
+                    coords.cast::<u16>() * resolution
+                        + quad.corner_deltas[corner]
+                            .cast::<u16>()
+                            .component_mul(&dims.coords.cast()),
+                    tex_index,
+                    quad.corner_tex_coords[corner]
+                        .coords
+                        .cast::<u16>()
+                        .component_mul(&tex_dims.coords.cast())
+                        .into(),
                     face,
                     corner_aos[corner],
                     corner_lights[corner],
@@ -82,22 +121,66 @@ impl BlockData {
             })
     }
 
-    pub fn tex_index(self) -> u8 {
-        self.model.tex_index
+    pub fn fluid_mesh(
+        self,
+        coords: Point3<u8>,
+        area: BlockArea,
+        area_light: &BlockAreaLight,
+    ) -> impl Iterator<Item = BlockVertex> {
+        let height = area.fluid_height(Default::default());
+        let is_externally_lit = self.is_externally_lit();
+        Enum::variants()
+            .filter_map(move |side| {
+                let delta = SIDE_DELTAS[side];
+                let neighbor = area[delta];
+                let is_same = neighbor.base() == area.block().base();
+                let heights = match side {
+                    Side::Top => [height; 2],
+                    Side::Bottom => [0; 2],
+                    _ => [area.fluid_height(delta), height],
+                };
+                let is_visible = match side {
+                    Side::Top => !is_same,
+                    Side::Bottom => !is_same && !neighbor.data().is_solid(),
+                    _ => heights[0] < heights[1] && !neighbor.data().is_solid(),
+                };
+                is_visible.then(|| {
+                    self.fluid_vertices(
+                        side,
+                        coords,
+                        heights,
+                        area.corner_aos(Some(side), is_externally_lit),
+                        area_light.corner_lights(Some(side), area),
+                    )
+                })
+            })
+            .flatten()
+    }
+
+    pub fn with_level(self, level: u8) -> Option<Block> {
+        self.kind().with_level(self.state as usize, level)
     }
 
-    pub fn hitbox(self, coords: Point3<i64>) -> Aabb {
-        self.model.hitbox(coords)
+    pub fn tex_index(self, side: Option<Side>) -> u16 {
+        self.model.tex_index(side)
+    }
+
+    pub fn hitboxes(self, coords: Point3<i64>) -> impl Iterator<Item = Aabb> {
+        self.model.hitboxes(coords)
     }
 
     pub fn flat_icon(self) -> Option<impl Iterator<Item = BlockVertex>> {
         let tex_idx = self.model.flat_icon()?;
         let corner_deltas = SIDE_CORNER_DELTAS[Side::Front];
+        let resolution = BlockVertex::RESOLUTION as u16;
         Some(CORNERS.into_iter().map(move |corner| {
             BlockVertex::new(
-                corner_deltas[corner].into(),
+                corner_deltas[corner]
+                    .cast::<u16>()
+                    .map(|c| c * resolution)
+                    .into(),
                 tex_idx,
-                CORNER_TEX_COORDS[corner],
+                CORNER_TEX_COORDS[corner].cast::<u16>() * resolution,
                 Default::default(),
                 Default::default(),
                 Default::default(),
@@ -105,6 +188,14 @@ impl BlockData {
         }))
     }
 
+    pub fn is_side_full(self, side: Side) -> bool {
+        self.model.is_side_full(side)
+    }
+
+    pub fn is_side_covered(self, side: Side, neighbor: Self) -> bool {
+        self.model.is_side_covered(side, neighbor.model)
+    }
+
     pub fn is_glowing(self) -> bool {
         self.luminance != Default::default()
     }
@@ -117,10 +208,45 @@ impl BlockData {
         !self.is_transparent()
     }
 
+    pub fn is_solid(self) -> bool {
+        self.is_opaque() && self.model.is_full()
+    }
+
     pub fn is_externally_lit(self) -> bool {
         !self.is_glowing() && self.light_filter == Default::default()
     }
 
+    fn fluid_vertices(
+        self,
+        side: Side,
+        coords: Point3<u8>,
+        [lower, upper]: [u8; 2],
+        corner_aos: EnumMap<Corner, u8>,
+        corner_lights: EnumMap<Corner, BlockLight>,
+    ) -> impl Iterator<Item = BlockVertex> {
+        let tex_index = self.model.tex_index(Some(side));
+        let corners = Self::corners(corner_aos, corner_lights);
+        let resolution = BlockVertex::RESOLUTION as u16;
+        let face = Some(side).into();
+        corners.into_iter().map(move |corner| {
+            let delta = SIDE_CORNER_DELTAS[side][corner].cast::<u16>();
+            let y = if delta.y == 0 { lower } else { upper } as u16;
+            let tex_coords = CORNER_TEX_COORDS[corner].cast::<u16>() * resolution;
+            BlockVertex::new(
+                coords.cast::<u16>() * resolution
+                    + vector![delta.x * resolution, y, delta.z * resolution],
+                tex_index,
+                match side {
+                    Side::Top | Side::Bottom => tex_coords,
+                    _ => point![tex_coords.x, resolution - y],
+                },
+                face,
+                corner_aos[corner],
+                corner_lights[corner],
+            )
+        })
+    }
+
     fn corners(
         corner_aos: EnumMap<Corner, u8>,
         corner_lights: EnumMap<Corner, BlockLight>,
@@ -137,15 +263,16 @@ impl BlockData {
     }
 }
 
-impl From<RawBlockData> for BlockData {
-    fn from(data: RawBlockData) -> Self {
-        Self {
-            model: data.model.into(),
-            luminance: data.luminance,
-            light_filter: data.light_filter,
-            requires_blending: data.requires_blending,
-            valid_surface: data.valid_surface.as_deref().map(|str| STR_TO_BLOCK[str]),
-        }
+impl Hash for BlockData {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.model.hash(state);
+        self.luminance.hash(state);
+        self.light_filter.hash(state);
+        self.requires_blending.hash(state);
+        self.valid_surface
+            .map(|block| &BLOCK_NAMES[block.0 as usize])
+            .hash(state);
+        self.fluid.hash(state);
     }
 }
 
@@ -168,13 +295,11 @@ struct RawBlockData {
     light_filter: Rgb<bool>,
     requires_blending: bool,
     valid_surface: Option<Arc<str>>,
+    fluid: Option<RawFluid>,
+    states: BTreeMap<Arc<str>, Box<[Arc<str>]>>,
 }
 
 impl RawBlockData {
-    fn tex_path(&self) -> &Arc<str> {
-        &self.model.tex_path
-    }
-
     fn deserialize_light_filter<'de, D>(deserializer: D) -> Result<Rgb<bool>, D::Error>
     where
         D: Deserializer<'de>,
@@ -191,6 +316,26 @@ impl RawBlockData {
     }
 }
 
+#[derive(Clone, Copy, Hash)]
+pub struct Fluid {
+    pub tick_delay: u16,
+    pub level: u8,
+    levels: u8,
+}
+
+impl Fluid {
+    const MAX_HEIGHT: u8 = BlockVertex::RESOLUTION - 2;
+
+    pub fn height(self) -> u8 {
+        ((self.levels - self.level) as u16 * Self::MAX_HEIGHT as u16 / self.levels as u16) as u8
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+struct RawFluid {
+    tick_delay: u16,
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy)]
 pub enum Face {
@@ -229,6 +374,17 @@ pub enum Side {
 }
 
 impl Side {
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Bottom => Self::Top,
+            Self::Front => Self::Back,
+            Self::Right => Self::Left,
+            Self::Back => Self::Front,
+            Self::Left => Self::Right,
+            Self::Top => Self::Bottom,
+        }
+    }
+
     pub fn points(self) -> impl Iterator<Item = (Point3<u8>, Point3<u8>)> {
         let masks = SIDE_MASKS[self];
         (0..Chunk::DIM as u8).flat_map(move |x| {
@@ -260,46 +416,97 @@ pub enum Component {
 }
 
 pub(super) static BLOCK_DATA: LazyLock<Box<[BlockData]>> = LazyLock::new(|| {
-    let mut data = Box::new_uninit_slice(STR_TO_BLOCK.len());
-    for (str, &Block(i)) in &*STR_TO_BLOCK {
-        data[i as usize].write(RAW_BLOCK_DATA[str].clone().into());
-    }
-    unsafe { data.assume_init() }
+    BLOCK_KINDS
+        .iter()
+        .enumerate()
+        .flat_map(|(i, kind)| {
+            let data = &RAW_BLOCK_DATA[kind.name()];
+            (0..kind.len()).map(move |state| BlockData::new(data, i, kind, state))
+        })
+        .collect()
 });
 
 pub static STR_TO_BLOCK: LazyLock<FxHashMap<Arc<str>, Block>> = LazyLock::new(|| {
-    let mut idx = Block::HARD_CODED_VALUES.len() as u8;
-    RAW_BLOCK_DATA
+    let mut blocks = BLOCK_NAMES
+        .iter()
+        .cloned()
+        .zip((0..).map(Block))
+        .collect::<FxHashMap<_, _>>();
+    for kind in BLOCK_KINDS.iter().filter(|kind| kind.has_properties()) {
+        let base = blocks[&kind.state_name(0)];
+        blocks.insert(kind.name().clone(), base);
+    }
+    blocks
+});
+
+pub static BLOCK_NAMES: LazyLock<Box<[Arc<str>]>> = LazyLock::new(|| {
+    BLOCK_KINDS
+        .iter()
+        .flat_map(|kind| (0..kind.len()).map(|state| kind.state_name(state)))
+        .collect()
+});
+
+pub(super) static BLOCK_KINDS: LazyLock<Box<[BlockKind]>> = LazyLock::new(|| {
+    let mut names = RAW_BLOCK_DATA
         .keys()
+        .filter(|str| !Block::HARD_CODED_VALUES.contains(&&***str))
         .cloned()
-        .map(|str| {
-            if let Some(i) = Block::HARD_CODED_VALUES.iter().position(|&s| *s == *str) {
-                (str, Block(i as u8))
-            } else {
-                let entry = (str, Block(idx));
-                idx += 1;
-                entry
-            }
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+
+    let mut len = 0;
+    let kinds = Block::HARD_CODED_VALUES
+        .iter()
+        .map(|&str| str.into())
+        .chain(names)
+        .map(|name: Arc<str>| {
+            let kind = BlockKind::new(
+                name.clone(),
+                Block(len as u16),
+                &RAW_BLOCK_DATA[&name].states,
+            );
+            len += kind.len();
+            kind
         })
-        .collect()
+        .collect();
+
+    assert!(
+        len <= Block::MAX_COUNT,
+        "block state count must not exceed {}",
+        Block::MAX_COUNT,
+    );
+
+    kinds
 });
 
 pub static TEX_PATHS: LazyLock<FxIndexSet<Arc<str>>> = LazyLock::new(|| {
-    RAW_BLOCK_DATA
+    let paths = RAW_BLOCK_DATA
         .values()
-        .map(|data| data.tex_path().clone())
+        .flat_map(|data| data.model.tex_paths().cloned())
+        .collect::<FxIndexSet<_>>();
+
+    assert!(
+        paths.len() <= BlockVertex::MAX_TEX_COUNT,
+        "texture count must not exceed {}",
+        BlockVertex::MAX_TEX_COUNT,
+    );
+
+    paths
+});
+
+pub static BLOCK_HASHES: LazyLock<Box<[u64]>> = LazyLock::new(|| {
+    BLOCK_DATA
+        .iter()
+        .map(|data| {
+            let mut hasher = StableHasher::default();
+            data.hash(&mut hasher);
+            hasher.finish()
+        })
         .collect()
 });
 
 static RAW_BLOCK_DATA: LazyLock<FxHashMap<Arc<str>, RawBlockData>> = LazyLock::new(|| {
-    let data =
-        utils::deserialize::<_, FxHashMap<Arc<_>, RawBlockData>>("assets/config/blocks.toml");
-
-    assert!(
-        data.len() <= Block::MAX_COUNT,
-        "block count must not exceed {}",
-        Block::MAX_COUNT,
-    );
+    let data = utils::deserialize::<_, FxHashMap<Arc<_>, RawBlockData>>(BLOCKS_PATH);
 
     if let Some(str) = Block::HARD_CODED_VALUES
         .iter()
@@ -389,7 +596,7 @@ pub static SIDE_MASKS: LazyLock<EnumMap<Side, Point3<(usize, usize)>>> = LazyLoc
     }
 });
 
-static SIDE_CORNER_DELTAS: LazyLock<EnumMap<Side, EnumMap<Corner, Vector3<u8>>>> =
+pub(super) static SIDE_CORNER_DELTAS: LazyLock<EnumMap<Side, EnumMap<Corner, Vector3<u8>>>> =
     LazyLock::new(|| {
         SIDE_CORNER_SIDES.map(|s1, corner_sides| {
             corner_sides.map(|_, [s2, s3]| {
@@ -434,3 +641,55 @@ const FLIPPED_CORNERS: [Corner; 6] = [
     Corner::UpperRight,
     Corner::UpperLeft,
 ];
+
+const BLOCKS_PATH: &str = "assets/config/blocks.toml";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tex_index(block: Block, side: Side) -> u16 {
+        block.data().model.tex_index(Some(side))
+    }
+
+    #[test]
+    fn grass_has_top_bottom_and_side_textures() {
+        let grass = STR_TO_BLOCK["grass"];
+        assert_ne!(tex_index(grass, Side::Top), tex_index(grass, Side::Front));
+        assert_ne!(tex_index(grass, Side::Top), tex_index(grass, Side::Bottom));
+        assert_ne!(
+            tex_index(grass, Side::Bottom),
+            tex_index(grass, Side::Front)
+        );
+        assert_eq!(tex_index(grass, Side::Front), tex_index(grass, Side::Left));
+    }
+
+    #[test]
+    fn log_ends_follow_its_axis() {
+        let log = STR_TO_BLOCK["log"];
+        let (ends, sides) = (tex_index(log, Side::Top), tex_index(log, Side::Front));
+        assert_ne!(ends, sides);
+        assert_eq!(tex_index(log, Side::Bottom), ends);
+
+        let end_side = |axis: usize| {
+            SIDE_DELTAS
+                .iter()
+                .find(|(_, delta)| delta[axis] == 1)
+                .map(|(side, _)| side)
+                .expect("every axis should have a positive side")
+        };
+        for axis in 0..3 {
+            let mut normal = Vector3::zeros();
+            normal[axis] = 1;
+            let oriented = log.oriented(normal, Vector3::x());
+            for side in Side::variants() {
+                let is_end = side == end_side(axis) || side == end_side(axis).opposite();
+                assert_eq!(
+                    tex_index(oriented, side),
+                    if is_end { ends } else { sides },
+                    "log side {side:?} with axis {axis}",
+                );
+            }
+        }
+    }
+}
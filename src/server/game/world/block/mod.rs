@@ -1,28 +1,68 @@
 pub mod area;
 pub mod data;
 pub mod model;
+pub mod state;
 
-use self::data::{BLOCK_DATA, BlockData};
+use self::data::{BLOCK_DATA, BLOCK_NAMES, BlockData, STR_TO_BLOCK};
 use super::action::BlockAction;
 use crate::shared::color::Rgb;
 use bitfield::bitfield;
-use serde::{Deserialize, Serialize};
-use std::{array, ops::Range};
+use nalgebra::Vector3;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Deserializer, Serialize, de};
+use std::{
+    array,
+    ops::{Deref, Range},
+    sync::Arc,
+};
 
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
-pub struct Block(u8);
+pub struct Block(u16);
 
 impl Block {
-    const MAX_COUNT: usize = u8::MAX as usize + 1;
+    const MAX_COUNT: usize = u16::MAX as usize + 1;
     const HARD_CODED_VALUES: &[&str] = &["air", "sand"];
     pub const AIR: Self = Self(0);
     pub const SAND: Self = Self(1);
 
+    pub fn from_name<E: de::Error>(str: &str) -> Result<Self, E> {
+        STR_TO_BLOCK.get(str).copied().ok_or_else(|| {
+            E::invalid_value(
+                de::Unexpected::Str(str),
+                &&*format!(
+                    "one of \"{}\"",
+                    BLOCK_NAMES
+                        .iter()
+                        .map(Deref::deref)
+                        .collect::<Vec<_>>()
+                        .join("\", \"")
+                ),
+            )
+        })
+    }
+
+    pub fn deserialize_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_name(&String::deserialize(deserializer)?)
+    }
+
+    pub fn is_valid(self) -> bool {
+        (self.0 as usize) < BLOCK_DATA.len()
+    }
+
     pub fn data(self) -> BlockData {
         unsafe { *BLOCK_DATA.get_unchecked(self.0 as usize) }
     }
 
+    pub fn base(self) -> Self {
+        Self(self.0 - self.data().state)
+    }
+
+    pub fn oriented(self, normal: Vector3<i64>, dir: Vector3<f32>) -> Self {
+        let data = self.data();
+        data.kind().oriented(data.state as usize, normal, dir)
+    }
+
     pub fn apply(&mut self, action: BlockAction) -> bool {
         if self.is_action_valid(action) {
             self.apply_unchecked(action);
@@ -43,11 +83,50 @@ impl Block {
         match (self, action) {
             (Self::AIR, BlockAction::Place(Self::AIR) | BlockAction::Destroy) => false,
             (Self::AIR, BlockAction::Place(_)) | (_, BlockAction::Destroy) => true,
-            (_, BlockAction::Place(_)) => false,
+            (_, BlockAction::Place(block)) => self
+                .data()
+                .fluid
+                .is_some_and(|fluid| fluid.level != 0 || block.data().fluid.is_none()),
         }
     }
 }
 
+pub struct BlockRemap {
+    to_local: Box<[Block]>,
+    to_remote: Box<[Option<Block>]>,
+}
+
+impl BlockRemap {
+    pub fn new(remote: &[Arc<str>]) -> Option<Self> {
+        let remote_blocks = remote
+            .iter()
+            .zip((0..).map(Block))
+            .collect::<FxHashMap<_, _>>();
+
+        (*remote != **BLOCK_NAMES).then(|| Self {
+            to_local: remote
+                .iter()
+                .map(|str| STR_TO_BLOCK.get(str).copied().unwrap_or(Block::AIR))
+                .collect(),
+            to_remote: BLOCK_NAMES
+                .iter()
+                .map(|str| remote_blocks.get(str).copied())
+                .collect(),
+        })
+    }
+
+    pub fn to_local(&self, block: Block) -> Block {
+        self.to_local
+            .get(block.0 as usize)
+            .copied()
+            .unwrap_or(Block::AIR)
+    }
+
+    pub fn to_remote(&self, block: Block) -> Option<Block> {
+        self.to_remote[block.0 as usize]
+    }
+}
+
 bitfield! {
     #[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
     pub struct BlockLight(u32);
@@ -117,3 +196,66 @@ impl From<[u8; Self::LEN]> for BlockLight {
         value
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remap_translates_reordered_registry() {
+        let mut remote = BLOCK_NAMES.to_vec();
+        remote.reverse();
+        let remap = BlockRemap::new(&remote).expect("reordered registry should need a remap");
+
+        for (i, str) in remote.iter().enumerate() {
+            let block = STR_TO_BLOCK[str];
+            assert!(remap.to_remote(block) == Some(Block(i as u16)));
+            assert!(remap.to_local(Block(i as u16)) == block);
+        }
+    }
+
+    #[test]
+    fn remap_handles_unknown_blocks() {
+        let mut remote = BLOCK_NAMES.to_vec();
+        let stone = remote
+            .iter()
+            .position(|str| &**str == "stone")
+            .expect("stone block should be configured");
+        remote[stone] = "unknown".into();
+        let remap = BlockRemap::new(&remote).expect("changed registry should need a remap");
+
+        assert!(remap.to_local(Block(stone as u16)) == Block::AIR);
+        assert!(remap.to_remote(STR_TO_BLOCK["stone"]).is_none());
+        assert!(remap.to_local(Block(remote.len() as u16)) == Block::AIR);
+    }
+
+    #[test]
+    fn remap_is_none_for_identical_registry() {
+        assert!(BlockRemap::new(&BLOCK_NAMES).is_none());
+    }
+
+    #[test]
+    fn place_into_fluid_is_limited() {
+        let stone = STR_TO_BLOCK["stone"];
+        let source = STR_TO_BLOCK["water"];
+        let flowing = source
+            .data()
+            .with_level(1)
+            .expect("water should have flowing levels");
+
+        assert!(source.is_action_valid(BlockAction::Place(stone)));
+        assert!(flowing.is_action_valid(BlockAction::Place(stone)));
+        assert!(flowing.is_action_valid(BlockAction::Place(source)));
+        assert!(!source.is_action_valid(BlockAction::Place(source)));
+        assert!(!source.is_action_valid(BlockAction::Place(flowing)));
+        assert!(!stone.is_action_valid(BlockAction::Place(source)));
+    }
+
+    #[test]
+    fn is_valid_rejects_unknown_ids() {
+        assert!(Block::AIR.is_valid());
+        assert!(Block(BLOCK_DATA.len() as u16 - 1).is_valid());
+        assert!(!Block(BLOCK_DATA.len() as u16).is_valid());
+        assert!(!Block(u16::MAX).is_valid());
+    }
+}
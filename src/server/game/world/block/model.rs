@@ -1,15 +1,21 @@
-use super::data::{Corner, Side, TEX_PATHS};
-use crate::shared::{
-    bound::Aabb,
-    enum_map::{Enum, EnumMap},
-    utils,
+use super::data::{Corner, SIDE_CORNER_DELTAS, SIDE_DELTAS, Side, TEX_PATHS};
+use crate::{
+    client::game::world::BlockVertex,
+    enum_map,
+    shared::{
+        bound::Aabb,
+        enum_map::{Enum, EnumMap},
+        utils,
+    },
 };
-use nalgebra::{Point3, Vector3};
+use nalgebra::{Matrix3, Point2, Point3, Vector3, matrix, point};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Deserializer};
 use std::{
+    hash::{Hash, Hasher},
     iter,
     ops::Deref,
+    path::PathBuf,
     sync::{Arc, LazyLock},
 };
 use walkdir::{DirEntry, WalkDir};
@@ -17,38 +23,72 @@ use walkdir::{DirEntry, WalkDir};
 #[derive(Clone, Copy)]
 pub struct Model {
     data: &'static ModelData,
-    pub tex_index: u8,
+    tex_indices: EnumMap<Option<Side>, u16>,
 }
 
 impl Model {
-    pub fn corner_deltas(self, side: Option<Side>) -> &'static CornerDeltas {
-        self.data.corner_deltas(side)
+    pub fn tex_index(self, side: Option<Side>) -> u16 {
+        self.tex_indices[side]
     }
 
-    pub fn hitbox(self, coords: Point3<i64>) -> Aabb {
-        self.data.hitbox(coords)
+    pub fn quads(self, side: Option<Side>) -> &'static [ModelQuad] {
+        &self.data.side_quads[side]
     }
 
-    pub fn flat_icon(self) -> Option<u8> {
-        self.data.has_flat_icon.then_some(self.tex_index)
+    pub fn is_side_covered(self, side: Side, neighbor: Self) -> bool {
+        let mask = self.data.occlusion[side];
+        mask != OcclusionMask::NONE
+            && neighbor.data.mirrored_occlusion[side.opposite()].covers(mask)
+    }
+
+    pub fn is_side_full(self, side: Side) -> bool {
+        self.data.full_sides[side]
+    }
+
+    pub fn is_full(self) -> bool {
+        self.data
+            .occlusion
+            .values()
+            .all(|&mask| mask == OcclusionMask::FULL)
+    }
+
+    pub fn hitboxes(self, coords: Point3<i64>) -> impl Iterator<Item = Aabb> {
+        self.data.hitboxes(coords)
+    }
+
+    pub fn flat_icon(self) -> Option<u16> {
+        self.data.has_flat_icon.then_some(self.tex_index(None))
     }
 }
 
-impl From<RawModel> for Model {
-    fn from(model: RawModel) -> Self {
+impl Model {
+    pub fn new(model: &RawModel, rotation: Rotation) -> Self {
         Self {
-            data: &MODEL_DATA[&model.variant],
-            tex_index: model.tex_index(),
+            data: &ROTATED_MODEL_DATA[&(model.variant.clone(), rotation)],
+            tex_indices: Enum::variants()
+                .map(|side: Option<Side>| {
+                    (side.map(|side| rotation.side(side)), model.tex_index(side))
+                })
+                .collect(),
         }
     }
 }
 
+impl Hash for Model {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
 #[derive(Default, Deserialize)]
-#[serde(from = "RawModelData")]
+#[serde(try_from = "RawModelData")]
 struct ModelData {
-    diagonal: Vector3<f32>,
+    hitboxes: Box<[Aabb]>,
     has_flat_icon: bool,
-    side_corner_deltas: SideCornerDeltas,
+    occlusion: EnumMap<Side, OcclusionMask>,
+    mirrored_occlusion: EnumMap<Side, OcclusionMask>,
+    side_quads: EnumMap<Option<Side>, Box<[ModelQuad]>>,
+    full_sides: EnumMap<Side, bool>,
 }
 
 type SideCornerDeltas = EnumMap<Option<Side>, Box<CornerDeltas>>;
@@ -56,31 +96,364 @@ type SideCornerDeltas = EnumMap<Option<Side>, Box<CornerDeltas>>;
 type CornerDeltas = [EnumMap<Corner, Vector3<u8>>];
 
 impl ModelData {
-    fn corner_deltas(&self, side: Option<Side>) -> &CornerDeltas {
-        &self.side_corner_deltas[side]
+    fn new(
+        hitboxes: Box<[Aabb]>,
+        has_flat_icon: bool,
+        occlusion: EnumMap<Side, OcclusionMask>,
+        side_corner_deltas: SideCornerDeltas,
+    ) -> Self {
+        let side_quads = side_corner_deltas.map(|_, corner_deltas| {
+            corner_deltas
+                .iter()
+                .map(|&corner_deltas| ModelQuad::new(corner_deltas))
+                .collect::<Box<_>>()
+        });
+        Self {
+            hitboxes,
+            has_flat_icon,
+            mirrored_occlusion: occlusion
+                .map(|side, mask| mask.remap(side, side.opposite(), |delta| delta)),
+            occlusion,
+            full_sides: enum_map! {
+                side => matches!(
+                    &*side_quads[Some(side)],
+                    [quad] if quad.corner_deltas == Self::full_corner_deltas(side),
+                )
+            },
+            side_quads,
+        }
     }
 
-    fn hitbox(&self, coords: Point3<i64>) -> Aabb {
-        Aabb::new(
-            coords.cast() + self.diagonal.map(|c| (1.0 - c) / 2.0),
-            self.diagonal,
+    fn hitboxes(&self, coords: Point3<i64>) -> impl Iterator<Item = Aabb> {
+        let delta = coords.coords.cast();
+        self.hitboxes
+            .iter()
+            .map(move |hitbox| hitbox.translate(delta))
+    }
+
+    fn rotated(&self, rotation: Rotation) -> Self {
+        Self::new(
+            self.hitboxes
+                .iter()
+                .map(|hitbox| rotation.hitbox(*hitbox))
+                .collect(),
+            self.has_flat_icon,
+            self.occlusion
+                .iter()
+                .map(|(side, mask)| {
+                    let rotated = rotation.side(side);
+                    (
+                        rotated,
+                        mask.remap(side, rotated, |delta| rotation.delta(delta)),
+                    )
+                })
+                .collect(),
+            self.side_quads
+                .iter()
+                .map(|(side, quads)| {
+                    (
+                        side.map(|side| rotation.side(side)),
+                        quads
+                            .iter()
+                            .map(|quad| rotation.corner_deltas(side, &quad.corner_deltas))
+                            .collect(),
+                    )
+                })
+                .collect(),
         )
     }
+
+    fn full_corner_deltas(side: Side) -> EnumMap<Corner, Vector3<u8>> {
+        SIDE_CORNER_DELTAS[side].map(|_, delta| delta * BlockVertex::RESOLUTION)
+    }
+}
+
+impl Hash for ModelData {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.hitboxes.len());
+        for hitbox in &self.hitboxes {
+            for c in hitbox.min.iter().chain(hitbox.max.iter()) {
+                state.write_u32(c.to_bits());
+            }
+        }
+        self.has_flat_icon.hash(state);
+        for mask in self.occlusion.values() {
+            state.write_u16(mask.0);
+        }
+        for quads in self.side_quads.values() {
+            state.write_usize(quads.len());
+            for quad in quads {
+                for delta in quad.corner_deltas.values() {
+                    state.write(delta.as_slice());
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ModelQuad {
+    pub facing: Option<Side>,
+    pub corner_deltas: EnumMap<Corner, Vector3<u8>>,
+    pub corner_tex_coords: EnumMap<Corner, Point2<u8>>,
 }
 
-impl From<RawModelData> for ModelData {
-    fn from(data: RawModelData) -> Self {
+impl ModelQuad {
+    fn new(corner_deltas: EnumMap<Corner, Vector3<u8>>) -> Self {
+        let facing = Self::facing(&corner_deltas);
+        let (side, projected) = match facing {
+            Some(side) => (side, corner_deltas),
+            None => (Side::Front, ModelData::full_corner_deltas(Side::Front)),
+        };
         Self {
-            diagonal: data.diagonal,
-            has_flat_icon: data.has_flat_icon,
-            side_corner_deltas: iter::zip(
-                Enum::variants(),
-                data.side_corner_deltas
-                    .into_values()
-                    .chain([data.internal_corner_deltas]),
+            facing,
+            corner_deltas,
+            corner_tex_coords: projected.map(|_, delta| {
+                let coords = project(side, delta);
+                point![coords.x, BlockVertex::RESOLUTION - coords.y]
+            }),
+        }
+    }
+
+    fn facing(corner_deltas: &EnumMap<Corner, Vector3<u8>>) -> Option<Side> {
+        let [lower_left, lower_right, upper_left] = [
+            corner_deltas[Corner::LowerLeft],
+            corner_deltas[Corner::LowerRight],
+            corner_deltas[Corner::UpperLeft],
+        ]
+        .map(|delta| delta.cast::<i16>());
+        let normal = (upper_left - lower_left).cross(&(lower_right - lower_left));
+        Enum::variants().find(|&side: &Side| {
+            let delta = SIDE_DELTAS[side].cast::<i16>();
+            delta.dot(&normal) > 0 && delta * delta.dot(&normal) == normal
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "RawOcclusionMask")]
+struct OcclusionMask(u16);
+
+impl OcclusionMask {
+    const DIM: u8 = 4;
+    const NONE: Self = Self(0);
+    const FULL: Self = Self(u16::MAX);
+
+    fn covers(self, other: Self) -> bool {
+        other.0 & !self.0 == 0
+    }
+
+    fn remap<F>(self, from: Side, to: Side, mut f: F) -> Self
+    where
+        F: FnMut(Vector3<u8>) -> Vector3<u8>,
+    {
+        let cell_dim = BlockVertex::RESOLUTION / Self::DIM;
+        Self((0..Self::DIM * Self::DIM).fold(0, |mask, i| {
+            if self.0 & 1 << i == 0 {
+                return mask;
+            }
+            let [row, col] = [i / Self::DIM, i % Self::DIM];
+            let center = point![col, Self::DIM - 1 - row].map(|c| c * cell_dim + cell_dim / 2);
+            let [col, row] = project(to, f(unproject(from, center)))
+                .map(|c| c / cell_dim)
+                .into();
+            mask | 1 << ((Self::DIM - 1 - row) * Self::DIM + col)
+        }))
+    }
+}
+
+impl TryFrom<RawOcclusionMask> for OcclusionMask {
+    type Error = String;
+
+    fn try_from(mask: RawOcclusionMask) -> Result<Self, Self::Error> {
+        match mask {
+            RawOcclusionMask::Keyword(keyword) if &*keyword == "full" => Ok(Self::FULL),
+            RawOcclusionMask::Keyword(keyword) if &*keyword == "none" => Ok(Self::NONE),
+            RawOcclusionMask::Rows(rows)
+                if rows.len() == Self::DIM as usize
+                    && rows.iter().all(|row| {
+                        row.len() == Self::DIM as usize && row.chars().all(|c| "#.".contains(c))
+                    }) =>
+            {
+                Ok(Self(
+                    rows.iter()
+                        .flat_map(|row| row.chars())
+                        .enumerate()
+                        .filter(|&(_, c)| c == '#')
+                        .fold(0, |mask, (i, _)| mask | 1 << i),
+                ))
+            }
+            _ => Err(format!(
+                "invalid occlusion mask, expected either \"full\", \"none\" or {0} rows of {0} '#' or '.' characters",
+                Self::DIM,
+            )),
+        }
+    }
+}
+
+fn project(side: Side, delta: Vector3<u8>) -> Point2<u8> {
+    let [origin, u, v] = frame(side);
+    let delta = delta.cast::<i16>() - origin;
+    point![delta.dot(&u), delta.dot(&v)].map(|c| c as u8)
+}
+
+fn unproject(side: Side, coords: Point2<u8>) -> Vector3<u8> {
+    let [origin, u, v] = frame(side);
+    (origin + u * coords.x as i16 + v * coords.y as i16).map(|c| c as u8)
+}
+
+fn frame(side: Side) -> [Vector3<i16>; 3] {
+    let corner_deltas = SIDE_CORNER_DELTAS[side].map(|_, delta| delta.cast::<i16>());
+    let origin = corner_deltas[Corner::LowerLeft];
+    [
+        origin * BlockVertex::RESOLUTION as i16,
+        corner_deltas[Corner::LowerRight] - origin,
+        corner_deltas[Corner::UpperLeft] - origin,
+    ]
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rotation(Matrix3<i8>);
+
+impl Rotation {
+    pub fn new(axis: usize, facing: Side) -> Self {
+        let tilt = [
+            matrix![0, 1, 0; -1, 0, 0; 0, 0, 1],
+            Matrix3::identity(),
+            matrix![1, 0, 0; 0, 0, -1; 0, 1, 0],
+        ][axis];
+        let spin = match facing {
+            Side::Front => Matrix3::identity(),
+            Side::Right => matrix![0, 0, -1; 0, 1, 0; 1, 0, 0],
+            Side::Back => matrix![-1, 0, 0; 0, 1, 0; 0, 0, -1],
+            Side::Left => matrix![0, 0, 1; 0, 1, 0; -1, 0, 0],
+            Side::Top | Side::Bottom => unreachable!(),
+        };
+        Self(spin * tilt)
+    }
+
+    fn all() -> impl Iterator<Item = Self> {
+        (0..3).flat_map(|axis| {
+            [Side::Front, Side::Right, Side::Back, Side::Left]
+                .into_iter()
+                .map(move |facing| Self::new(axis, facing))
+        })
+    }
+
+    fn side(self, side: Side) -> Side {
+        let delta = self.0 * SIDE_DELTAS[side];
+        Enum::variants()
+            .find(|&side| SIDE_DELTAS[side] == delta)
+            .unwrap_or_else(|| unreachable!())
+    }
+
+    fn corner_deltas(
+        self,
+        side: Option<Side>,
+        corner_deltas: &EnumMap<Corner, Vector3<u8>>,
+    ) -> EnumMap<Corner, Vector3<u8>> {
+        let Some(side) = side else {
+            return corner_deltas.map(|_, delta| self.delta(delta));
+        };
+
+        let rotated = self.side(side);
+        corner_deltas
+            .iter()
+            .map(|(corner, &delta)| {
+                let target = self.delta(ModelData::full_corner_deltas(side)[corner]);
+                let corner = Enum::variants()
+                    .find(|&corner| ModelData::full_corner_deltas(rotated)[corner] == target)
+                    .unwrap_or_else(|| unreachable!());
+                (corner, self.delta(delta))
+            })
+            .collect()
+    }
+
+    fn hitbox(self, hitbox: Aabb) -> Aabb {
+        let center = Vector3::repeat(0.5);
+        let rotation = self.0.cast::<f32>();
+        Aabb::from_corners(
+            (rotation * (hitbox.min.coords - center) + center).into(),
+            (rotation * (hitbox.max.coords - center) + center).into(),
+        )
+    }
+
+    fn delta(self, delta: Vector3<u8>) -> Vector3<u8> {
+        let size = Vector3::repeat(BlockVertex::RESOLUTION as i8);
+        let delta = delta.cast::<i8>() * 2 - size;
+        ((self.0 * delta + size) / 2).map(|c| c as u8)
+    }
+}
+
+impl TryFrom<RawModelData> for ModelData {
+    type Error = String;
+
+    fn try_from(data: RawModelData) -> Result<Self, Self::Error> {
+        let side_corner_deltas = iter::zip(
+            Enum::variants(),
+            data.side_corner_deltas
+                .into_values()
+                .chain([data.internal_corner_deltas]),
+        )
+        .map(|(side, corner_deltas)| {
+            let corner_deltas = corner_deltas
+                .iter()
+                .map(|corner_deltas| Self::corner_deltas(side, corner_deltas))
+                .collect::<Result<_, _>>()?;
+            Ok((side, corner_deltas))
+        })
+        .collect::<Result<SideCornerDeltas, Self::Error>>()?;
+
+        Ok(Self::new(
+            data.hitboxes
+                .into_iter()
+                .map(|hitbox| Aabb::from_corners(hitbox.min, hitbox.max))
+                .collect(),
+            data.has_flat_icon,
+            data.occlusion,
+            side_corner_deltas,
+        ))
+    }
+}
+
+impl ModelData {
+    fn corner_deltas(
+        side: Option<Side>,
+        corner_deltas: &EnumMap<Corner, Vector3<f32>>,
+    ) -> Result<EnumMap<Corner, Vector3<u8>>, String> {
+        let resolution = BlockVertex::RESOLUTION as f32;
+        let plane = side.map(|side| {
+            let axis = SIDE_DELTAS[side].iamax();
+            (
+                side,
+                axis,
+                Self::full_corner_deltas(side)[Corner::LowerLeft][axis],
             )
-            .collect(),
+        });
+        let mut deltas = EnumMap::from_fn(|_| Vector3::zeros());
+        for (corner, &delta) in corner_deltas.iter() {
+            let scaled = delta * resolution;
+            if scaled
+                .iter()
+                .any(|&c| !(0.0..=resolution).contains(&c) || c.fract() != 0.0)
+            {
+                return Err(format!(
+                    "invalid corner delta {:?}, expected components in [0, 1] that are multiples of 1/{}",
+                    <[f32; 3]>::from(delta),
+                    BlockVertex::RESOLUTION,
+                ));
+            }
+            deltas[corner] = scaled.map(|c| c as u8);
+            if let Some((side, axis, c)) = plane
+                && deltas[corner][axis] != c
+            {
+                return Err(format!(
+                    "corner delta {:?} of side \"{side:?}\" does not lie on that side",
+                    <[f32; 3]>::from(delta),
+                ));
+            }
         }
+        Ok(deltas)
     }
 }
 
@@ -90,14 +463,18 @@ pub struct RawModel {
     #[serde(rename = "model", deserialize_with = "RawModel::deserialize_variant")]
     variant: Arc<str>,
     #[serde(rename = "texture")]
-    pub tex_path: Arc<str>,
+    textures: RawTextures,
 }
 
 impl RawModel {
-    fn tex_index(&self) -> u8 {
+    pub fn tex_paths(&self) -> impl Iterator<Item = &Arc<str>> {
+        Enum::variants().map(|side| self.textures.tex_path(side))
+    }
+
+    fn tex_index(&self, side: Option<Side>) -> u16 {
         TEX_PATHS
-            .get_index_of(&self.tex_path)
-            .unwrap_or_else(|| unreachable!()) as u8
+            .get_index_of(self.textures.tex_path(side))
+            .unwrap_or_else(|| unreachable!()) as u16
     }
 
     fn deserialize_variant<'de, D>(deserializer: D) -> Result<Arc<str>, D::Error>
@@ -125,12 +502,56 @@ impl RawModel {
 
 impl Default for RawModel {
     fn default() -> Self {
-        static DEFAULT_TEX_PATH: LazyLock<Arc<str>> =
-            LazyLock::new(|| "missing_texture.png".into());
-
         Self {
             variant: DEFAULT_VARIANT.clone(),
-            tex_path: DEFAULT_TEX_PATH.clone(),
+            textures: RawTextures::Uniform(DEFAULT_TEX_PATH.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum RawTextures {
+    Uniform(Arc<str>),
+    PerSide(FxHashMap<TextureKey, Arc<str>>),
+}
+
+impl RawTextures {
+    fn tex_path(&self, side: Option<Side>) -> &Arc<str> {
+        match self {
+            Self::Uniform(path) => path,
+            Self::PerSide(paths) => TextureKey::fallbacks(side)
+                .iter()
+                .find_map(|key| paths.get(key))
+                .unwrap_or(&DEFAULT_TEX_PATH),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TextureKey {
+    Bottom,
+    Front,
+    Right,
+    Back,
+    Left,
+    Top,
+    Sides,
+    Ends,
+    All,
+}
+
+impl TextureKey {
+    fn fallbacks(side: Option<Side>) -> &'static [Self] {
+        match side {
+            Some(Side::Bottom) => &[Self::Bottom, Self::Ends, Self::All],
+            Some(Side::Front) => &[Self::Front, Self::Sides, Self::All],
+            Some(Side::Right) => &[Self::Right, Self::Sides, Self::All],
+            Some(Side::Back) => &[Self::Back, Self::Sides, Self::All],
+            Some(Side::Left) => &[Self::Left, Self::Sides, Self::All],
+            Some(Side::Top) => &[Self::Top, Self::Ends, Self::All],
+            None => &[Self::Sides, Self::All],
         }
     }
 }
@@ -138,13 +559,23 @@ impl Default for RawModel {
 #[derive(Default, Deserialize)]
 #[serde(default)]
 struct RawModelData {
-    diagonal: Vector3<f32>,
+    hitboxes: Vec<Aabb>,
     has_flat_icon: bool,
-    side_corner_deltas: EnumMap<Side, Box<CornerDeltas>>,
-    internal_corner_deltas: Box<CornerDeltas>,
+    occlusion: EnumMap<Side, OcclusionMask>,
+    side_corner_deltas: EnumMap<Side, Box<RawCornerDeltas>>,
+    internal_corner_deltas: Box<RawCornerDeltas>,
 }
 
-static MODEL_DATA: LazyLock<FxHashMap<Arc<str>, ModelData>> = LazyLock::new(|| {
+type RawCornerDeltas = [EnumMap<Corner, Vector3<f32>>];
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawOcclusionMask {
+    Keyword(Box<str>),
+    Rows(Vec<Box<str>>),
+}
+
+fn model_paths() -> impl Iterator<Item = PathBuf> {
     fn is_hidden(entry: &DirEntry) -> bool {
         entry
             .file_name()
@@ -152,21 +583,26 @@ static MODEL_DATA: LazyLock<FxHashMap<Arc<str>, ModelData>> = LazyLock::new(|| {
             .is_some_and(|s| s.starts_with('.'))
     }
 
-    let data = WalkDir::new("assets/config/models")
+    WalkDir::new("assets/config/models")
         .follow_links(true)
+        .sort_by_file_name()
         .into_iter()
         .filter_entry(|entry| !is_hidden(entry))
         .filter_map(Result::ok)
         .filter(|entry| entry.path().extension().is_some_and(|s| s == "toml"))
-        .map(|entry| {
-            let path = entry.path();
+        .map(DirEntry::into_path)
+}
+
+static MODEL_DATA: LazyLock<FxHashMap<Arc<str>, ModelData>> = LazyLock::new(|| {
+    let data = model_paths()
+        .map(|path| {
             (
                 path.file_stem()
                     .unwrap_or_else(|| unreachable!())
                     .to_str()
                     .unwrap_or_else(|| panic!("{path:?} should have a valid UTF-8 stem"))
                     .into(),
-                utils::deserialize(path),
+                utils::deserialize(&path),
             )
         })
         .collect::<FxHashMap<_, _>>();
@@ -180,4 +616,17 @@ static MODEL_DATA: LazyLock<FxHashMap<Arc<str>, ModelData>> = LazyLock::new(|| {
     data
 });
 
+static ROTATED_MODEL_DATA: LazyLock<FxHashMap<(Arc<str>, Rotation), ModelData>> =
+    LazyLock::new(|| {
+        MODEL_DATA
+            .iter()
+            .flat_map(|(variant, data)| {
+                Rotation::all()
+                    .map(|rotation| ((variant.clone(), rotation), data.rotated(rotation)))
+            })
+            .collect()
+    });
+
 static DEFAULT_VARIANT: LazyLock<Arc<str>> = LazyLock::new(|| "cube".into());
+
+static DEFAULT_TEX_PATH: LazyLock<Arc<str>> = LazyLock::new(|| "missing_texture.png".into());
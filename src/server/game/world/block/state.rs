@@ -0,0 +1,195 @@
+use super::{
+    Block,
+    data::{SIDE_DELTAS, Side},
+    model::Rotation,
+};
+use nalgebra::Vector3;
+use std::{collections::BTreeMap, sync::Arc};
+
+pub struct BlockKind {
+    name: Arc<str>,
+    base: Block,
+    properties: Box<[Property]>,
+}
+
+impl BlockKind {
+    pub(super) fn new(
+        name: Arc<str>,
+        base: Block,
+        properties: &BTreeMap<Arc<str>, Box<[Arc<str>]>>,
+    ) -> Self {
+        let mut stride = 1;
+        let properties = properties
+            .iter()
+            .map(|(property, values)| {
+                Self::validate(&name, property, values);
+                let property = Property {
+                    name: property.clone(),
+                    values: values.clone(),
+                    stride,
+                };
+                stride *= values.len();
+                property
+            })
+            .collect();
+
+        Self {
+            name,
+            base,
+            properties,
+        }
+    }
+
+    pub fn name(&self) -> &Arc<str> {
+        &self.name
+    }
+
+    pub fn has_properties(&self) -> bool {
+        !self.properties.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.properties
+            .iter()
+            .map(|property| property.values.len())
+            .product()
+    }
+
+    pub fn state_name(&self, state: usize) -> Arc<str> {
+        if self.properties.is_empty() {
+            return self.name.clone();
+        }
+
+        format!(
+            "{}[{}]",
+            self.name,
+            self.properties
+                .iter()
+                .map(|property| format!("{}={}", property.name, property.value(state)))
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+        .into()
+    }
+
+    pub fn rotation(&self, state: usize) -> Rotation {
+        let axis = self.value(state, "axis").map_or(1, |value| {
+            AXES.iter()
+                .position(|axis| **axis == **value)
+                .unwrap_or_else(|| unreachable!())
+        });
+        let facing = self.value(state, "facing").map_or(Side::Front, |value| {
+            FACINGS
+                .iter()
+                .find(|(facing, _)| **facing == **value)
+                .map(|&(_, side)| side)
+                .unwrap_or_else(|| unreachable!())
+        });
+        Rotation::new(axis, facing)
+    }
+
+    pub fn oriented(&self, state: usize, normal: Vector3<i64>, dir: Vector3<f32>) -> Block {
+        let state = self
+            .with_value(state, "axis", AXES[normal.iamax()])
+            .unwrap_or(state);
+
+        let mut facings = FACINGS;
+        facings.sort_by(|(_, a), (_, b)| {
+            let facing = |side: Side| SIDE_DELTAS[side].cast::<f32>().dot(&-dir);
+            facing(*b).total_cmp(&facing(*a))
+        });
+        let state = facings
+            .iter()
+            .find_map(|(facing, _)| self.with_value(state, "facing", facing))
+            .unwrap_or(state);
+
+        Block(self.base.0 + state as u16)
+    }
+
+    pub fn level(&self, state: usize) -> Option<u8> {
+        self.property("level")
+            .map(|property| property.index(state) as u8)
+    }
+
+    pub fn levels(&self) -> Option<u8> {
+        self.property("level")
+            .map(|property| property.values.len() as u8)
+    }
+
+    pub fn with_level(&self, state: usize, level: u8) -> Option<Block> {
+        self.with_value(state, "level", &level.to_string())
+            .map(|state| Block(self.base.0 + state as u16))
+    }
+
+    fn value(&self, state: usize, name: &str) -> Option<&Arc<str>> {
+        self.property(name).map(|property| property.value(state))
+    }
+
+    fn with_value(&self, state: usize, name: &str, value: &str) -> Option<usize> {
+        let property = self.property(name)?;
+        let index = property.values.iter().position(|v| **v == *value)?;
+        Some(state - property.index(state) * property.stride + index * property.stride)
+    }
+
+    fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| *property.name == *name)
+    }
+
+    fn validate(block: &str, property: &str, values: &[Arc<str>]) {
+        assert!(
+            !values.is_empty(),
+            "property \"{property}\" of block \"{block}\" must have at least one value",
+        );
+
+        if property == "level" {
+            if let Some((level, value)) = values
+                .iter()
+                .enumerate()
+                .find(|(level, value)| ***value != *level.to_string())
+            {
+                panic!("invalid level \"{value}\" of block \"{block}\", expected \"{level}\"");
+            }
+            return;
+        }
+
+        let expected = match property {
+            "axis" => &AXES[..],
+            "facing" => &FACINGS.map(|(facing, _)| facing)[..],
+            _ => return,
+        };
+
+        if let Some(value) = values.iter().find(|value| !expected.contains(&&***value)) {
+            panic!(
+                "invalid {property} \"{value}\" of block \"{block}\", expected one of \"{}\"",
+                expected.join("\", \""),
+            );
+        }
+    }
+}
+
+struct Property {
+    name: Arc<str>,
+    values: Box<[Arc<str>]>,
+    stride: usize,
+}
+
+impl Property {
+    fn index(&self, state: usize) -> usize {
+        state / self.stride % self.values.len()
+    }
+
+    fn value(&self, state: usize) -> &Arc<str> {
+        &self.values[self.index(state)]
+    }
+}
+
+const AXES: [&str; 3] = ["x", "y", "z"];
+
+const FACINGS: [(&str, Side); 4] = [
+    ("front", Side::Front),
+    ("right", Side::Right),
+    ("back", Side::Back),
+    ("left", Side::Left),
+];
@@ -7,20 +7,9 @@ use crate::{
     shared::utils,
 };
 use nalgebra::{Point3, Vector3, point, vector};
-use serde::{
-    Deserialize, Deserializer, Serialize, Serializer,
-    de::{SeqAccess, Visitor},
-    ser::SerializeSeq as _,
-};
-use std::{
-    fmt::{self, Formatter},
-    marker::PhantomData,
-    mem::{self, MaybeUninit},
-    ops::{Index, IndexMut, Range},
-    slice,
-};
+use std::ops::{Index, IndexMut, Range};
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default)]
 pub struct ChunkArea(ChunkAreaDataStore<Block>);
 
 impl ChunkArea {
@@ -78,7 +67,7 @@ impl IndexMut<Vector3<i8>> for ChunkArea {
     }
 }
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default)]
 pub struct ChunkAreaLight(ChunkAreaDataStore<BlockLight>);
 
 impl ChunkAreaLight {
@@ -105,14 +94,6 @@ impl IndexMut<Vector3<i8>> for ChunkAreaLight {
 struct ChunkAreaDataStore<T>([[[T; ChunkArea::DIM]; ChunkArea::DIM]; ChunkArea::DIM]);
 
 impl<T> ChunkAreaDataStore<T> {
-    fn first(&self) -> &T {
-        &self.0[0][0][0]
-    }
-
-    fn values(&self) -> slice::Iter<T> {
-        self.0.as_flattened().as_flattened().iter()
-    }
-
     fn index_unchecked(delta: Vector3<i8>) -> [usize; 3] {
         delta
             .map(|c| (c + BlockArea::PADDING as i8) as usize)
@@ -120,22 +101,6 @@ impl<T> ChunkAreaDataStore<T> {
     }
 }
 
-impl<T: PartialEq> ChunkAreaDataStore<T> {
-    fn packed_len(&self) -> usize {
-        let mut prev = self.first();
-        let mut len = 1;
-
-        for value in self.values() {
-            if prev != value {
-                prev = value;
-                len += 1;
-            }
-        }
-
-        len
-    }
-}
-
 impl<T> Index<Vector3<i8>> for ChunkAreaDataStore<T> {
     type Output = T;
 
@@ -151,59 +116,3 @@ impl<T> IndexMut<Vector3<i8>> for ChunkAreaDataStore<T> {
         &mut self.0[x][y][z]
     }
 }
-
-impl<T: PartialEq + Serialize> Serialize for ChunkAreaDataStore<T> {
-    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        const { assert!(ChunkArea::DIM.pow(3) <= u16::MAX as usize) };
-
-        let mut seq = serializer.serialize_seq(Some(self.packed_len()))?;
-        let mut prev = self.first();
-        let mut count = 0u16;
-
-        for value in self.values() {
-            if prev == value {
-                count += 1;
-            } else {
-                seq.serialize_element(&(prev, count))?;
-                prev = value;
-                count = 1;
-            }
-        }
-
-        seq.serialize_element(&(prev, count))?;
-
-        seq.end()
-    }
-}
-
-impl<'de, T: Deserialize<'de> + Clone> Deserialize<'de> for ChunkAreaDataStore<T> {
-    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        struct SeqVisitor<T>(PhantomData<fn() -> ChunkAreaDataStore<T>>);
-
-        impl<'de, T: Deserialize<'de> + Clone> Visitor<'de> for SeqVisitor<T> {
-            type Value = ChunkAreaDataStore<T>;
-
-            fn expecting(&self, f: &mut Formatter) -> fmt::Result {
-                write!(f, "a sequence of (value, count) pairs")
-            }
-
-            fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Self::Value, S::Error> {
-                const { assert!(!mem::needs_drop::<T>()) };
-
-                let mut uninit = [const { MaybeUninit::uninit() }; ChunkArea::DIM.pow(3)];
-                let mut cur = 0;
-
-                while let Some((value, count)) = seq.next_element::<(T, u16)>()? {
-                    let count = count as usize;
-                    MaybeUninit::fill(&mut uninit[cur..cur + count], value);
-                    cur += count;
-                }
-
-                assert!(cur == uninit.len());
-                Ok(ChunkAreaDataStore(unsafe { mem::transmute_copy(&uninit) }))
-            }
-        }
-
-        deserializer.deserialize_seq(SeqVisitor(PhantomData))
-    }
-}
@@ -0,0 +1,97 @@
+use super::structure::Structure;
+use crate::{server::game::world::block::Block, shared::utils};
+use nalgebra::Point2;
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use std::{ops::Range, sync::LazyLock};
+
+#[derive(Deserialize)]
+pub struct Biome {
+    temperature: f64,
+    humidity: f64,
+    pub height: Range<i32>,
+    #[serde(deserialize_with = "Block::deserialize_name")]
+    pub surface: Block,
+    #[serde(deserialize_with = "Block::deserialize_name")]
+    pub subsurface: Block,
+    #[serde(default)]
+    decorations: Vec<Decoration>,
+    #[serde(default)]
+    structures: Vec<StructureConfig>,
+}
+
+impl Biome {
+    pub fn weights(climate: Point2<f64>, blend: f64) -> impl Iterator<Item = (&'static Self, f64)> {
+        let distances = BIOMES
+            .iter()
+            .map(|biome| (climate - biome.climate()).norm())
+            .collect::<Vec<_>>();
+        let min_distance = distances.iter().copied().fold(f64::INFINITY, f64::min);
+        BIOMES
+            .iter()
+            .zip(distances)
+            .map(move |(biome, distance)| {
+                (biome, (1.0 - (distance - min_distance) / blend).max(0.0))
+            })
+            .filter(|&(_, weight)| weight > 0.0)
+    }
+
+    pub fn decoration(&self, value: f64) -> Option<Block> {
+        let mut acc = 0.0;
+        self.decorations
+            .iter()
+            .find(|decoration| {
+                acc += decoration.density;
+                value < acc
+            })
+            .map(|decoration| decoration.block)
+            .filter(|block| {
+                block
+                    .data()
+                    .valid_surface
+                    .is_none_or(|valid| valid == self.surface)
+            })
+    }
+
+    pub fn structure(&self, value: f64) -> Option<Structure> {
+        let mut acc = 0.0;
+        self.structures
+            .iter()
+            .find(|structure| {
+                acc += structure.density;
+                value < acc
+            })
+            .map(|structure| structure.structure)
+    }
+
+    pub fn structure_density(&self) -> f64 {
+        self.structures
+            .iter()
+            .map(|structure| structure.density)
+            .sum()
+    }
+
+    fn climate(&self) -> Point2<f64> {
+        Point2::new(self.temperature, self.humidity)
+    }
+}
+
+#[derive(Deserialize)]
+struct Decoration {
+    #[serde(deserialize_with = "Block::deserialize_name")]
+    block: Block,
+    density: f64,
+}
+
+#[derive(Deserialize)]
+struct StructureConfig {
+    #[serde(flatten)]
+    structure: Structure,
+    density: f64,
+}
+
+pub static BIOMES: LazyLock<Box<[Biome]>> = LazyLock::new(|| {
+    let biomes = utils::deserialize::<_, FxHashMap<String, Biome>>("assets/config/biomes.toml");
+    assert!(!biomes.is_empty(), "biome count must be positive");
+    biomes.into_values().collect()
+});
@@ -0,0 +1,51 @@
+use super::ChunkGenerator;
+use crate::server::{
+    SERVER_CONFIG,
+    game::world::{block::Block, chunk::Chunk},
+};
+use nalgebra::Point3;
+use serde::Deserialize;
+use std::iter;
+
+pub struct FlatGenerator(Box<[Block]>);
+
+impl FlatGenerator {
+    pub fn new() -> Self {
+        Self(
+            SERVER_CONFIG
+                .generator
+                .flat
+                .layers
+                .iter()
+                .flat_map(|layer| iter::repeat_n(layer.block, layer.depth))
+                .collect(),
+        )
+    }
+}
+
+impl ChunkGenerator for FlatGenerator {
+    fn generate(&self, coords: Point3<i32>) -> Chunk {
+        let start = SERVER_CONFIG.generator.flat.start;
+        let min_y = coords.y * Chunk::DIM as i32;
+        Chunk::from_fn(|block_coords| {
+            let y = min_y + block_coords.y as i32 - start;
+            usize::try_from(y)
+                .ok()
+                .and_then(|y| self.0.get(y).copied())
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FlatConfig {
+    start: i32,
+    layers: Vec<FlatLayer>,
+}
+
+#[derive(Deserialize)]
+struct FlatLayer {
+    #[serde(deserialize_with = "Block::deserialize_name")]
+    block: Block,
+    depth: usize,
+}
@@ -0,0 +1,63 @@
+pub mod biome;
+pub mod flat;
+pub mod noise;
+pub mod structure;
+pub mod terrain;
+pub mod void;
+
+use self::{
+    flat::{FlatConfig, FlatGenerator},
+    noise::{NoiseConfig, NoiseGenerator},
+    terrain::{TerrainConfig, TerrainGenerator},
+    void::VoidGenerator,
+};
+use super::Chunk;
+use crate::server::{SERVER_CONFIG, game::world::block::Block};
+use nalgebra::Point3;
+use serde::Deserialize;
+
+pub trait ChunkGenerator: Send + Sync {
+    fn generate(&self, coords: Point3<i32>) -> Chunk;
+
+    fn structures(&self, _: Point3<i32>) -> Vec<(Point3<i64>, Block)> {
+        vec![]
+    }
+}
+
+impl dyn ChunkGenerator {
+    pub fn new(seed: u32) -> Box<Self> {
+        match SERVER_CONFIG.generator.preset {
+            GeneratorPreset::Noise => Box::new(NoiseGenerator::new(seed)),
+            GeneratorPreset::Flat => Box::new(FlatGenerator::new()),
+            GeneratorPreset::Void => Box::new(VoidGenerator),
+            GeneratorPreset::Terrain => Box::new(TerrainGenerator::new(seed)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum GeneratorPreset {
+    Noise,
+    Flat,
+    Void,
+    Terrain,
+}
+
+#[derive(Deserialize)]
+pub struct GeneratorConfig {
+    preset: GeneratorPreset,
+    noise: NoiseConfig,
+    flat: FlatConfig,
+    terrain: TerrainConfig,
+}
+
+fn random<const N: usize>(values: [u64; N]) -> f64 {
+    let hash = values.into_iter().fold(0u64, |acc, value| {
+        let mut x = (acc ^ value).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        x ^ (x >> 31)
+    });
+    hash as f64 / u64::MAX as f64
+}
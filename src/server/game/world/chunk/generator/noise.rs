@@ -0,0 +1,47 @@
+use super::ChunkGenerator;
+use crate::{
+    server::{
+        SERVER_CONFIG,
+        game::world::{block::Block, chunk::Chunk},
+    },
+    shared::utils,
+};
+use nalgebra::Point3;
+use noise::{NoiseFn as _, Simplex};
+use serde::Deserialize;
+use std::ops::Range;
+
+pub struct NoiseGenerator(Simplex);
+
+impl NoiseGenerator {
+    pub fn new(seed: u32) -> Self {
+        Self(Simplex::new(seed))
+    }
+}
+
+impl ChunkGenerator for NoiseGenerator {
+    fn generate(&self, coords: Point3<i32>) -> Chunk {
+        let config = &SERVER_CONFIG.generator.noise;
+        if config.y_range.contains(&coords.y) {
+            Chunk::from_fn(|block_coords| {
+                let coords = utils::coords((coords, block_coords)).cast() * config.frequency;
+                if self.0.get(coords.into()) > config.threshold {
+                    config.block
+                } else {
+                    Block::AIR
+                }
+            })
+        } else {
+            Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NoiseConfig {
+    y_range: Range<i32>,
+    frequency: f64,
+    threshold: f64,
+    #[serde(deserialize_with = "Block::deserialize_name")]
+    block: Block,
+}
@@ -0,0 +1,61 @@
+use super::random;
+use crate::server::game::world::block::Block;
+use nalgebra::{Point3, vector};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Structure {
+    Tree {
+        #[serde(deserialize_with = "Block::deserialize_name")]
+        log: Block,
+        #[serde(deserialize_with = "Block::deserialize_name")]
+        leaves: Block,
+    },
+    Ruin {
+        #[serde(deserialize_with = "Block::deserialize_name")]
+        wall: Block,
+    },
+}
+
+impl Structure {
+    pub const MAX_EXTENT: usize = 8;
+
+    pub fn blocks(self, origin: Point3<i64>, seed: u64) -> Vec<(Point3<i64>, Block)> {
+        let mut blocks = vec![];
+        match self {
+            Self::Tree { log, leaves } => {
+                let height = 4 + (random([seed, 0]) * 3.0) as i64;
+                for dy in height - 2..=height + 1 {
+                    let radius: i64 = if dy < height { 2 } else { 1 };
+                    for dx in -radius..=radius {
+                        for dz in -radius..=radius {
+                            if dx.abs() != radius || dz.abs() != radius || dy == height - 2 {
+                                blocks.push((origin + vector![dx, dy, dz], leaves));
+                            }
+                        }
+                    }
+                }
+                for dy in 0..height {
+                    blocks.push((origin + vector![0, dy, 0], log));
+                }
+            }
+            Self::Ruin { wall } => {
+                let size = 5 + (random([seed, 0]) * 3.0) as i64;
+                for dx in 0..size {
+                    for dz in 0..size {
+                        if (dx == 0 || dz == 0 || dx == size - 1 || dz == size - 1)
+                            && random([seed, 1, dx as u64, dz as u64]) > 0.3
+                        {
+                            let height = 1 + (random([seed, 2, dx as u64, dz as u64]) * 3.0) as i64;
+                            for dy in 0..height {
+                                blocks.push((origin + vector![dx, dy, dz], wall));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        blocks
+    }
+}
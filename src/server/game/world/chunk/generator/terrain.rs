@@ -0,0 +1,380 @@
+use super::{
+    ChunkGenerator,
+    biome::{BIOMES, Biome},
+    random,
+    structure::Structure,
+};
+use crate::{
+    server::{
+        SERVER_CONFIG,
+        game::world::{block::Block, chunk::Chunk},
+    },
+    shared::utils,
+};
+use nalgebra::{Point2, Point3, point};
+use noise::{Fbm, MultiFractal, NoiseFn, Simplex};
+use serde::{Deserialize, Deserializer, de};
+use std::array;
+
+pub struct TerrainGenerator {
+    seed: u32,
+    max_structure_density: f64,
+    height: Fbm<Simplex>,
+    temperature: Fbm<Simplex>,
+    humidity: Fbm<Simplex>,
+    worms: [Simplex; 2],
+    cheese: Fbm<Simplex>,
+}
+
+impl TerrainGenerator {
+    pub fn new(seed: u32) -> Self {
+        let config = &SERVER_CONFIG.generator.terrain;
+        Self {
+            seed,
+            max_structure_density: BIOMES
+                .iter()
+                .map(Biome::structure_density)
+                .fold(0.0, f64::max),
+            height: Fbm::new(seed)
+                .set_octaves(config.octaves)
+                .set_frequency(config.frequency)
+                .set_persistence(config.persistence),
+            temperature: Fbm::new(seed.wrapping_add(1))
+                .set_octaves(2)
+                .set_frequency(config.climate_frequency),
+            humidity: Fbm::new(seed.wrapping_add(2))
+                .set_octaves(2)
+                .set_frequency(config.climate_frequency),
+            worms: [
+                Simplex::new(seed.wrapping_add(3)),
+                Simplex::new(seed.wrapping_add(4)),
+            ],
+            cheese: Fbm::new(seed.wrapping_add(5))
+                .set_octaves(3)
+                .set_frequency(config.caves.cheese_frequency),
+        }
+    }
+
+    fn column(&self, coords: Point2<i64>) -> Column {
+        let config = &SERVER_CONFIG.generator.terrain;
+        let climate = point![
+            Self::sample(&self.temperature, coords),
+            Self::sample(&self.humidity, coords),
+        ];
+        let noise = Self::sample(&self.height, coords);
+
+        let mut total_weight = 0.0;
+        let mut height = 0.0;
+        let mut biomes = Vec::new();
+        for (biome, weight) in Biome::weights(climate, config.blend) {
+            let start = biome.height.start as f64;
+            let end = biome.height.end as f64;
+            total_weight += weight;
+            height += (start + noise * (end - start)) * weight;
+            biomes.push((biome, total_weight));
+        }
+
+        let dither = self.random(coords, 0) * total_weight;
+        let biome = biomes
+            .iter()
+            .find(|&&(_, acc)| dither < acc)
+            .or(biomes.last())
+            .map(|&(biome, _)| biome)
+            .unwrap_or_else(|| panic!("no biome within blend distance of {climate:?}"));
+        Column {
+            height: (height / total_weight) as i32,
+            biome,
+            decoration: self.random(coords, 1),
+            structure: biome.structure(self.random(coords, 2)),
+        }
+    }
+
+    fn is_carved(&self, coords: Point3<i64>, height: i32) -> bool {
+        let config = &SERVER_CONFIG.generator.terrain;
+        let is_sea_floor =
+            height <= config.sea_level && coords.y as i32 > height - config.caves.sea_floor_depth;
+        !is_sea_floor && self.is_cave(coords, height)
+    }
+
+    fn is_cave(&self, coords: Point3<i64>, height: i32) -> bool {
+        let caves = &SERVER_CONFIG.generator.terrain.caves;
+        let y = coords.y as i32;
+        if y > height || y < caves.floor {
+            return false;
+        }
+
+        let point = coords.cast::<f64>();
+        let worm_point =
+            point![point.x, point.y * caves.worm_squash, point.z] * caves.worm_frequency;
+        let is_worm = self
+            .worms
+            .iter()
+            .all(|noise| noise.get(worm_point.into()).abs() < caves.worm_width);
+        is_worm
+            || y <= height - caves.cheese_depth
+                && self.cheese.get(point.into()) > caves.cheese_threshold
+    }
+
+    fn sample<N: NoiseFn<f64, 2>>(noise: &N, coords: Point2<i64>) -> f64 {
+        (noise.get(coords.cast().into()) * 0.5 + 0.5).clamp(0.0, 1.0)
+    }
+
+    fn random(&self, coords: Point2<i64>, salt: u64) -> f64 {
+        random([self.seed as u64, coords.x as u64, coords.y as u64, salt])
+    }
+}
+
+impl ChunkGenerator for TerrainGenerator {
+    fn generate(&self, coords: Point3<i32>) -> Chunk {
+        let config = &SERVER_CONFIG.generator.terrain;
+        let min_y = coords.y * Chunk::DIM as i32;
+        if min_y > config.max_height() + 1 && min_y > config.sea_level {
+            return Default::default();
+        }
+
+        let columns: [[Column; Chunk::DIM]; Chunk::DIM] = array::from_fn(|x| {
+            array::from_fn(|z| self.column(utils::coords((coords.xz(), point![x as u8, z as u8]))))
+        });
+
+        Chunk::from_fn(|block_coords| {
+            let column = &columns[block_coords.x as usize][block_coords.z as usize];
+            let coords = utils::coords((coords, block_coords));
+            let y = coords.y as i32;
+            if self.is_carved(coords, column.height)
+                || y == column.height + 1
+                    && y > config.sea_level
+                    && self.is_carved(point![coords.x, coords.y - 1, coords.z], column.height)
+            {
+                Block::AIR
+            } else {
+                config.block(column, y)
+            }
+        })
+    }
+
+    fn structures(&self, coords: Point3<i32>) -> Vec<(Point3<i64>, Block)> {
+        let config = &SERVER_CONFIG.generator.terrain;
+        let min_y = coords.y as i64 * Chunk::DIM as i64;
+        let y_range = min_y..min_y + Chunk::DIM as i64;
+        let mut blocks = vec![];
+        for x in 0..Chunk::DIM as u8 {
+            for z in 0..Chunk::DIM as u8 {
+                let column_coords = utils::coords((coords.xz(), point![x, z]));
+                let value = self.random(column_coords, 2);
+                if value >= self.max_structure_density {
+                    continue;
+                }
+
+                let column = self.column(column_coords);
+                let y = column.height as i64 + 1;
+                if y_range.contains(&y)
+                    && column.height > config.sea_level
+                    && !self.is_carved(
+                        point![column_coords.x, y - 1, column_coords.y],
+                        column.height,
+                    )
+                    && let Some(structure) = column.structure
+                {
+                    blocks.extend(
+                        structure
+                            .blocks(point![column_coords.x, y, column_coords.y], value.to_bits()),
+                    );
+                }
+            }
+        }
+        blocks
+    }
+}
+
+struct Column {
+    height: i32,
+    biome: &'static Biome,
+    decoration: f64,
+    structure: Option<Structure>,
+}
+
+#[derive(Deserialize)]
+pub struct TerrainConfig {
+    sea_level: i32,
+    frequency: f64,
+    octaves: usize,
+    persistence: f64,
+    climate_frequency: f64,
+    #[serde(deserialize_with = "TerrainConfig::deserialize_blend")]
+    blend: f64,
+    layers: LayerConfig,
+    caves: CaveConfig,
+}
+
+impl TerrainConfig {
+    fn max_height(&self) -> i32 {
+        BIOMES
+            .iter()
+            .map(|biome| biome.height.end)
+            .max()
+            .unwrap_or_else(|| panic!("biome count must be positive"))
+    }
+
+    fn deserialize_blend<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        let blend = f64::deserialize(deserializer)?;
+        if blend > 0.0 && blend.is_finite() {
+            Ok(blend)
+        } else {
+            Err(de::Error::invalid_value(
+                de::Unexpected::Float(blend),
+                &"a positive finite number",
+            ))
+        }
+    }
+
+    fn block(&self, column: &Column, y: i32) -> Block {
+        let Column {
+            height,
+            biome,
+            decoration,
+            structure,
+        } = *column;
+        let layers = &self.layers;
+        let is_underwater = height <= self.sea_level;
+        if y > height {
+            if y <= self.sea_level {
+                layers.sea
+            } else if y == height + 1 && !is_underwater && structure.is_none() {
+                biome.decoration(decoration).unwrap_or(Block::AIR)
+            } else {
+                Block::AIR
+            }
+        } else if y > height - layers.subsurface_depth {
+            if is_underwater {
+                layers.shore
+            } else if y == height {
+                biome.surface
+            } else {
+                biome.subsurface
+            }
+        } else {
+            layers.stone
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LayerConfig {
+    subsurface_depth: i32,
+    #[serde(deserialize_with = "Block::deserialize_name")]
+    stone: Block,
+    #[serde(deserialize_with = "Block::deserialize_name")]
+    shore: Block,
+    #[serde(deserialize_with = "Block::deserialize_name")]
+    sea: Block,
+}
+
+#[derive(Deserialize)]
+struct CaveConfig {
+    floor: i32,
+    worm_frequency: f64,
+    worm_squash: f64,
+    worm_width: f64,
+    cheese_frequency: f64,
+    cheese_threshold: f64,
+    cheese_depth: i32,
+    sea_floor_depth: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(blend: &str) -> Result<TerrainConfig, toml::de::Error> {
+        toml::from_str(&format!(
+            r#"
+            sea_level = 16
+            frequency = 0.005
+            octaves = 4
+            persistence = 0.5
+            climate_frequency = 0.001
+            blend = {blend}
+            layers = {{ subsurface_depth = 4, stone = "stone", shore = "sand", sea = "water" }}
+
+            [caves]
+            floor = -60
+            worm_frequency = 0.015
+            worm_squash = 2.0
+            worm_width = 0.05
+            cheese_frequency = 0.02
+            cheese_threshold = 0.6
+            cheese_depth = 8
+            sea_floor_depth = 4
+            "#
+        ))
+    }
+
+    #[test]
+    fn config_accepts_positive_blend() {
+        assert_eq!(config("0.1").unwrap().blend, 0.1);
+    }
+
+    #[test]
+    fn config_rejects_non_positive_blend() {
+        assert!(config("0.0").is_err());
+        assert!(config("-0.1").is_err());
+        assert!(config("nan").is_err());
+    }
+
+    #[test]
+    fn structure_origins_skip_decorations() {
+        let generator = TerrainGenerator::new(7);
+        let config = &SERVER_CONFIG.generator.terrain;
+        let column = (0..256)
+            .flat_map(|x| (0..256).map(move |z| point![x, z]))
+            .map(|coords| generator.column(coords))
+            .find(|column| column.structure.is_some() && column.height > config.sea_level)
+            .expect("some column should have a structure");
+        assert!(config.block(&column, column.height + 1) == Block::AIR);
+    }
+
+    #[test]
+    fn carved_sea_floors_stay_flooded() {
+        let generator = TerrainGenerator::new(7);
+        let config = &SERVER_CONFIG.generator.terrain;
+        let dim = Chunk::DIM as i32;
+        let y_range =
+            (config.sea_level - 2 * dim).div_euclid(dim)..=config.sea_level.div_euclid(dim);
+        let mut carved_floors = 0;
+        for x in -4..4 {
+            for z in -4..4 {
+                let chunks = y_range
+                    .clone()
+                    .map(|y| (y, generator.generate(point![x, y, z])))
+                    .collect::<Vec<_>>();
+                for bx in 0..Chunk::DIM as u8 {
+                    for bz in 0..Chunk::DIM as u8 {
+                        let coords = utils::coords((point![x, z], point![bx, bz]));
+                        let column = generator.column(coords);
+                        if column.height > config.sea_level {
+                            continue;
+                        }
+                        let floor = column.height - config.caves.sea_floor_depth + 1;
+                        for &(cy, ref chunk) in &chunks {
+                            for by in 0..Chunk::DIM as u8 {
+                                let y = cy * dim + by as i32;
+                                if (floor..=config.sea_level).contains(&y) {
+                                    assert!(
+                                        chunk[point![bx, by, bz]] != Block::AIR,
+                                        "air at {coords:?} {y} below sea level",
+                                    );
+                                }
+                            }
+                        }
+                        if (floor..=column.height).any(|y| {
+                            generator.is_cave(point![coords.x, y as i64, coords.y], column.height)
+                        }) {
+                            carved_floors += 1;
+                        }
+                    }
+                }
+            }
+        }
+        assert!(carved_floors > 0, "no carved sea floor was sampled");
+    }
+}
@@ -0,0 +1,11 @@
+use super::ChunkGenerator;
+use crate::server::game::world::chunk::Chunk;
+use nalgebra::Point3;
+
+pub struct VoidGenerator;
+
+impl ChunkGenerator for VoidGenerator {
+    fn generate(&self, _: Point3<i32>) -> Chunk {
+        Default::default()
+    }
+}
@@ -1,47 +1,66 @@
 pub mod area;
 pub mod generator;
+pub mod palette;
 
+use self::palette::PaletteStore;
 use super::{
     action::BlockAction,
-    block::{Block, BlockLight},
+    block::{Block, BlockLight, BlockRemap},
 };
 use crate::shared::{
     bound::{Aabb, BoundingSphere},
     utils,
 };
 use nalgebra::{Point3, Vector3, point};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     array, mem,
     ops::{BitOrAssign, Index, IndexMut},
 };
 
+pub type ChunkBlocks = PaletteStore<Block, { Chunk::DIM.pow(3) }>;
+
 #[derive(Default)]
 pub struct Chunk {
-    blocks: ChunkDataStore<Block>,
+    blocks: ChunkBlocks,
     non_air_count: u16,
     glowing_count: u16,
+    fluid_count: u16,
 }
 
 impl Chunk {
     pub const DIM: usize = 16;
 
     fn from_fn<F: FnMut(Point3<u8>) -> Block>(mut f: F) -> Self {
+        Self::from_blocks(PaletteStore::from_fn(|i| f(Self::coords(i))))
+    }
+
+    pub fn from_blocks(blocks: ChunkBlocks) -> Self {
         let mut non_air_count = 0;
         let mut glowing_count = 0;
+        let mut fluid_count = 0;
+        for &block in blocks.values() {
+            non_air_count += (block != Block::AIR) as u16;
+            glowing_count += block.data().is_glowing() as u16;
+            fluid_count += block.data().fluid.is_some() as u16;
+        }
         Self {
-            blocks: ChunkDataStore::from_fn(|coords| {
-                let block = f(coords);
-                non_air_count += (block != Block::AIR) as u16;
-                glowing_count += block.data().is_glowing() as u16;
-                block
-            }),
+            blocks,
             non_air_count,
             glowing_count,
+            fluid_count,
         }
     }
 
+    pub fn block_store(&self) -> &ChunkBlocks {
+        &self.blocks
+    }
+
     pub fn blocks(&self) -> impl Iterator<Item = (Point3<u8>, &Block)> {
-        self.blocks.values()
+        self.blocks
+            .values()
+            .enumerate()
+            .map(|(i, block)| (Self::coords(i), block))
     }
 
     pub fn is_empty(&self) -> bool {
@@ -52,12 +71,15 @@ impl Chunk {
         self.glowing_count != 0
     }
 
+    pub fn has_fluids(&self) -> bool {
+        self.fluid_count != 0
+    }
+
     pub fn apply(&mut self, coords: Point3<u8>, action: BlockAction) -> bool {
-        let block = &mut self.blocks[coords];
-        let prev = *block;
-        if block.apply(action) {
-            let curr = *block;
-            self.adjust_counts(prev, curr);
+        let prev = self[coords];
+        let mut curr = prev;
+        if curr.apply(action) {
+            self.set(coords, prev, curr);
             true
         } else {
             false
@@ -65,10 +87,14 @@ impl Chunk {
     }
 
     pub fn apply_unchecked(&mut self, coords: Point3<u8>, action: BlockAction) {
-        let block = &mut self.blocks[coords];
-        let prev = *block;
-        block.apply_unchecked(action);
-        let curr = *block;
+        let prev = self[coords];
+        let mut curr = prev;
+        curr.apply_unchecked(action);
+        self.set(coords, prev, curr);
+    }
+
+    fn set(&mut self, coords: Point3<u8>, prev: Block, curr: Block) {
+        self.blocks.set(Self::index(coords), curr);
         self.adjust_counts(prev, curr);
     }
 
@@ -77,6 +103,8 @@ impl Chunk {
         self.non_air_count += (curr != Block::AIR) as u16;
         self.glowing_count -= prev.data().is_glowing() as u16;
         self.glowing_count += curr.data().is_glowing() as u16;
+        self.fluid_count -= prev.data().fluid.is_some() as u16;
+        self.fluid_count += curr.data().fluid.is_some() as u16;
     }
 
     fn bounding_box(coords: Point3<i32>) -> Aabb {
@@ -89,13 +117,38 @@ impl Chunk {
     pub fn bounding_sphere(coords: Point3<i32>) -> BoundingSphere {
         Self::bounding_box(coords).into()
     }
+
+    fn index(coords: Point3<u8>) -> usize {
+        (coords.x as usize * Self::DIM + coords.y as usize) * Self::DIM + coords.z as usize
+    }
+
+    fn coords(index: usize) -> Point3<u8> {
+        point![
+            index / Self::DIM.pow(2),
+            index / Self::DIM % Self::DIM,
+            index % Self::DIM,
+        ]
+        .cast()
+    }
 }
 
 impl Index<Point3<u8>> for Chunk {
     type Output = Block;
 
     fn index(&self, coords: Point3<u8>) -> &Self::Output {
-        &self.blocks[coords]
+        self.blocks.get(Self::index(coords))
+    }
+}
+
+impl Serialize for Chunk {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.blocks.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Chunk {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::from_blocks(PaletteStore::deserialize(deserializer)?))
     }
 }
 
@@ -143,6 +196,40 @@ impl BitOrAssign<BlockLight> for ChunkLight {
     }
 }
 
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ChunkSnapshot {
+    blocks: ChunkBlocks,
+    lights: PaletteStore<BlockLight, { Chunk::DIM.pow(3) }>,
+}
+
+impl ChunkSnapshot {
+    pub fn new(chunk: Option<&Chunk>, light: Option<&ChunkLight>) -> Self {
+        Self {
+            blocks: chunk.map_or_else(Default::default, |chunk| chunk.blocks.clone()),
+            lights: light.map_or_else(Default::default, |light| {
+                PaletteStore::from_fn(|i| light[Chunk::coords(i)])
+            }),
+        }
+    }
+
+    pub fn block(&self, coords: Point3<u8>) -> Block {
+        *self.blocks.get(Chunk::index(coords))
+    }
+
+    pub fn light(&self, coords: Point3<u8>) -> BlockLight {
+        *self.lights.get(Chunk::index(coords))
+    }
+
+    pub fn set(&mut self, coords: Point3<u8>, block: Block, light: BlockLight) {
+        self.blocks.set(Chunk::index(coords), block);
+        self.lights.set(Chunk::index(coords), light);
+    }
+
+    pub fn remap(&mut self, remap: &BlockRemap) {
+        self.blocks = self.blocks.map(|block| remap.to_local(block));
+    }
+}
+
 #[derive(Default)]
 pub struct ChunkDataStore<T>([[[T; Chunk::DIM]; Chunk::DIM]; Chunk::DIM]);
 
@@ -153,17 +240,6 @@ impl<T> ChunkDataStore<T> {
         }))
     }
 
-    fn values(&self) -> impl Iterator<Item = (Point3<u8>, &T)> {
-        self.0.iter().enumerate().flat_map(|(x, values)| {
-            values.iter().enumerate().flat_map(move |(y, values)| {
-                values
-                    .iter()
-                    .enumerate()
-                    .map(move |(z, value)| (point![x, y, z].cast(), value))
-            })
-        })
-    }
-
     fn apply<F: FnMut(&mut T)>(&mut self, f: F) {
         self.0.iter_mut().flatten().flatten().for_each(f);
     }
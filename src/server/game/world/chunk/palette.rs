@@ -0,0 +1,283 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::mem;
+
+#[derive(Clone)]
+pub enum PaletteStore<T, const LEN: usize> {
+    Uniform(T),
+    Packed(PackedStore<T, LEN>),
+}
+
+impl<T: Copy + PartialEq, const LEN: usize> PaletteStore<T, LEN> {
+    pub fn from_fn<F: FnMut(usize) -> T>(mut f: F) -> Self {
+        let mut palette = vec![];
+        let mut prev = 0;
+        let indices = (0..LEN)
+            .map(|i| {
+                let value = f(i);
+                if palette.get(prev) != Some(&value) {
+                    prev = palette.iter().position(|v| *v == value).unwrap_or_else(|| {
+                        palette.push(value);
+                        palette.len() - 1
+                    });
+                }
+                prev
+            })
+            .collect::<Vec<_>>();
+
+        if let [value] = *palette {
+            Self::Uniform(value)
+        } else {
+            Self::Packed(PackedStore::new(palette, indices))
+        }
+    }
+
+    pub fn get(&self, index: usize) -> &T {
+        match self {
+            Self::Uniform(value) => value,
+            Self::Packed(store) => store.get(index),
+        }
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        if let Self::Packed(store) = self
+            && store.is_full(&value)
+        {
+            *self = self.compacted();
+        }
+
+        match self {
+            Self::Uniform(prev) if *prev == value => {}
+            Self::Uniform(prev) => {
+                let mut indices = vec![0; LEN];
+                indices[index] = 1;
+                *self = Self::Packed(PackedStore::new(vec![*prev, value], indices));
+            }
+            Self::Packed(store) => store.set(index, value),
+        }
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        (0..LEN).map(|i| self.get(i))
+    }
+
+    pub fn compacted(&self) -> Self {
+        match self {
+            Self::Uniform(value) => Self::Uniform(*value),
+            Self::Packed(store) => Self::from_fn(|i| *store.get(i)),
+        }
+    }
+
+    pub fn map<F: FnMut(T) -> T>(&self, mut f: F) -> Self {
+        match self {
+            Self::Uniform(value) => Self::Uniform(f(*value)),
+            Self::Packed(PackedStore { palette, words }) => Self::Packed(PackedStore {
+                palette: palette.iter().map(|&value| f(value)).collect(),
+                words: words.clone(),
+            }),
+        }
+    }
+}
+
+impl<T: Default, const LEN: usize> Default for PaletteStore<T, LEN> {
+    fn default() -> Self {
+        Self::Uniform(Default::default())
+    }
+}
+
+impl<T: Serialize, const LEN: usize> Serialize for PaletteStore<T, LEN> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Uniform(value) => RawPaletteStore::Uniform(value),
+            Self::Packed(PackedStore { palette, words }) => RawPaletteStore::Packed(palette, words),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const LEN: usize> Deserialize<'de> for PaletteStore<T, LEN> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match RawPaletteStore::<T, Vec<T>, Box<[u64]>>::deserialize(deserializer)? {
+            RawPaletteStore::Uniform(value) => Ok(Self::Uniform(value)),
+            RawPaletteStore::Packed(palette, words) => {
+                let store = PackedStore { palette, words };
+                let len = PackedStore::<T, LEN>::word_count(store.bits());
+                if store.palette.len() < 2 {
+                    Err(de::Error::invalid_length(
+                        store.palette.len(),
+                        &"a palette of at least two values",
+                    ))
+                } else if store.words.len() != len {
+                    Err(de::Error::invalid_length(
+                        store.words.len(),
+                        &&*format!("{len} words"),
+                    ))
+                } else if (0..LEN).any(|i| store.index(i) >= store.palette.len()) {
+                    Err(de::Error::custom("palette index out of range"))
+                } else {
+                    Ok(Self::Packed(store))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PackedStore<T, const LEN: usize> {
+    palette: Vec<T>,
+    words: Box<[u64]>,
+}
+
+impl<T, const LEN: usize> PackedStore<T, LEN> {
+    fn get(&self, index: usize) -> &T {
+        &self.palette[self.index(index)]
+    }
+
+    fn index(&self, index: usize) -> usize {
+        self.index_with(index, self.bits())
+    }
+
+    fn index_with(&self, index: usize, bits: usize) -> usize {
+        let per_word = u64::BITS as usize / bits;
+        let shift = index % per_word * bits;
+        (self.words[index / per_word] >> shift & Self::mask(bits)) as usize
+    }
+
+    fn set_index(&mut self, index: usize, value: usize) {
+        let bits = self.bits();
+        let per_word = u64::BITS as usize / bits;
+        let shift = index % per_word * bits;
+        let word = &mut self.words[index / per_word];
+        *word = *word & !(Self::mask(bits) << shift) | (value as u64) << shift;
+    }
+
+    fn bits(&self) -> usize {
+        Self::bits_for(self.palette.len())
+    }
+
+    fn bits_for(len: usize) -> usize {
+        (usize::BITS - len.saturating_sub(1).leading_zeros()).max(1) as usize
+    }
+
+    fn mask(bits: usize) -> u64 {
+        (1 << bits) - 1
+    }
+
+    fn word_count(bits: usize) -> usize {
+        LEN.div_ceil(u64::BITS as usize / bits)
+    }
+}
+
+impl<T: PartialEq, const LEN: usize> PackedStore<T, LEN> {
+    fn new(palette: Vec<T>, indices: Vec<usize>) -> Self {
+        let mut store = Self {
+            words: vec![0; Self::word_count(Self::bits_for(palette.len()))].into(),
+            palette,
+        };
+        for (i, index) in indices.into_iter().enumerate() {
+            store.set_index(i, index);
+        }
+        store
+    }
+
+    fn is_full(&self, value: &T) -> bool {
+        Self::bits_for(self.palette.len() + 1) != self.bits() && !self.palette.contains(value)
+    }
+
+    fn set(&mut self, index: usize, value: T) {
+        let i = if let Some(i) = self.palette.iter().position(|v| *v == value) {
+            i
+        } else {
+            let bits = self.bits();
+            self.palette.push(value);
+            if self.bits() != bits {
+                let indices = (0..LEN).map(|i| self.index_with(i, bits)).collect();
+                *self = Self::new(mem::take(&mut self.palette), indices);
+            }
+            self.palette.len() - 1
+        };
+        self.set_index(index, i);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum RawPaletteStore<T, P, W> {
+    Uniform(T),
+    Packed(P, W),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Store = PaletteStore<u16, 100>;
+
+    fn packed(store: &Store) -> &PackedStore<u16, 100> {
+        match store {
+            PaletteStore::Uniform(_) => panic!("expected a packed store"),
+            PaletteStore::Packed(store) => store,
+        }
+    }
+
+    #[test]
+    fn from_fn_round_trips_values() {
+        let store = Store::from_fn(|i| (i % 7) as u16);
+        assert_eq!(packed(&store).palette.len(), 7);
+        assert_eq!(packed(&store).bits(), 3);
+        assert!((0..100).all(|i| *store.get(i) == (i % 7) as u16));
+    }
+
+    #[test]
+    fn from_fn_collapses_to_uniform() {
+        assert!(matches!(Store::from_fn(|_| 5), PaletteStore::Uniform(5)));
+    }
+
+    #[test]
+    fn set_grows_bit_width() {
+        let mut store = Store::default();
+        for i in 0..100 {
+            store.set(i, i as u16);
+        }
+        assert_eq!(packed(&store).bits(), 7);
+        assert!((0..100).all(|i| *store.get(i) == i as u16));
+    }
+
+    #[test]
+    fn set_compacts_when_palette_overflows() {
+        let mut store = Store::from_fn(|i| (i % 4) as u16);
+        for i in 0..100 {
+            store.set(i, 0);
+        }
+        store.set(0, 4);
+        assert_eq!(packed(&store).palette, [0, 4]);
+        assert!((1..100).all(|i| *store.get(i) == 0));
+    }
+
+    #[test]
+    fn compacted_drops_unused_values() {
+        let mut store = Store::from_fn(|i| (i % 3) as u16);
+        for i in 0..100 {
+            store.set(i, 1);
+        }
+        assert_eq!(packed(&store).palette.len(), 3);
+        assert!(matches!(store.compacted(), PaletteStore::Uniform(1)));
+    }
+
+    #[test]
+    fn serde_round_trips() {
+        let store = Store::from_fn(|i| (i * i % 11) as u16);
+        let bytes = bincode::serialize(&store).unwrap();
+        let store = bincode::deserialize::<Store>(&bytes).unwrap();
+        assert!((0..100).all(|i| *store.get(i) == (i * i % 11) as u16));
+    }
+
+    #[test]
+    fn deserialize_rejects_out_of_range_index() {
+        let store = PackedStore::<u16, 100>::new(vec![0, 1, 2], vec![3; 100]);
+        let bytes = bincode::serialize(&RawPaletteStore::<u16, _, _>::Packed(
+            &store.palette,
+            &store.words,
+        ))
+        .unwrap();
+        assert!(bincode::deserialize::<Store>(&bytes).is_err());
+    }
+}
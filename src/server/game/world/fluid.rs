@@ -0,0 +1,256 @@
+use super::{
+    ChunkStore,
+    action::BlockAction,
+    block::{
+        Block,
+        data::{SIDE_DELTAS, Side},
+    },
+};
+use crate::shared::utils;
+use nalgebra::{Point3, Vector3};
+use rustc_hash::FxHashSet;
+use std::{collections::BTreeMap, iter};
+
+#[derive(Default)]
+pub struct FluidStore {
+    ticks: u64,
+    scheduled: BTreeMap<u64, FxHashSet<Point3<i64>>>,
+}
+
+impl FluidStore {
+    pub fn schedule<P>(&mut self, chunks: &ChunkStore, points: P)
+    where
+        P: IntoIterator<Item = Point3<i64>>,
+    {
+        for coords in points {
+            let points = iter::once(coords)
+                .chain(SIDE_DELTAS.values().map(|delta| coords + delta.cast()))
+                .collect::<Vec<_>>();
+            if let Some(tick_delay) = points
+                .iter()
+                .filter_map(|&coords| chunks.block(coords).data().fluid)
+                .map(|fluid| fluid.tick_delay)
+                .min()
+            {
+                self.scheduled
+                    .entry(self.ticks + tick_delay as u64)
+                    .or_default()
+                    .extend(points);
+            }
+        }
+    }
+
+    pub fn load(&mut self, chunks: &ChunkStore, points: &[Point3<i32>]) {
+        let points = points
+            .iter()
+            .flat_map(|&coords| {
+                let inner = chunks
+                    .get(coords)
+                    .filter(|chunk| chunk.has_fluids())
+                    .into_iter()
+                    .flat_map(move |chunk| {
+                        chunk
+                            .blocks()
+                            .map(move |(block_coords, _)| utils::coords((coords, block_coords)))
+                    });
+                let border = SIDE_DELTAS
+                    .into_iter()
+                    .filter_map(move |(side, delta)| {
+                        let coords = coords + delta.cast();
+                        chunks
+                            .get(coords)
+                            .filter(|chunk| chunk.has_fluids())
+                            .map(|_| (side, coords))
+                    })
+                    .flat_map(|(side, coords)| {
+                        side.points()
+                            .map(move |(_, block_coords)| utils::coords((coords, block_coords)))
+                    });
+                inner.chain(border)
+            })
+            .filter(|&coords| !Self::is_settled(chunks, coords))
+            .collect::<Vec<_>>();
+        self.schedule(chunks, points);
+    }
+
+    pub fn evict(&mut self, points: &FxHashSet<Point3<i32>>) {
+        self.scheduled.retain(|_, scheduled| {
+            scheduled.retain(|&coords| !points.contains(&utils::chunk_coords(coords)));
+            !scheduled.is_empty()
+        });
+    }
+
+    pub fn tick(&mut self, chunks: &ChunkStore) -> Vec<(Point3<i64>, BlockAction)> {
+        self.ticks += 1;
+
+        let mut points = FxHashSet::default();
+        while let Some(entry) = self.scheduled.first_entry()
+            && *entry.key() <= self.ticks
+        {
+            points.extend(entry.remove());
+        }
+
+        points
+            .into_iter()
+            .filter_map(|coords| Some((coords, Self::flow(chunks, coords)?)))
+            .collect()
+    }
+
+    fn is_settled(chunks: &ChunkStore, coords: Point3<i64>) -> bool {
+        match chunks.block(coords).data().fluid {
+            Some(fluid) if fluid.level == 0 => iter::once(Side::Bottom)
+                .chain(HORIZONTAL_SIDES)
+                .all(|side| chunks.block(coords + SIDE_DELTAS[side].cast()) != Block::AIR),
+            Some(_) => false,
+            None => true,
+        }
+    }
+
+    fn flow(chunks: &ChunkStore, coords: Point3<i64>) -> Option<BlockAction> {
+        let block = chunks.block(coords);
+        let fluid = match block.data().fluid {
+            Some(fluid) if fluid.level == 0 => return None,
+            Some(_) => block,
+            None if block == Block::AIR => FLOW_SIDES
+                .into_iter()
+                .map(|side| chunks.block(coords + SIDE_DELTAS[side].cast()))
+                .find(|neighbor| neighbor.data().fluid.is_some())?,
+            None => return None,
+        };
+        let is_same = |neighbor: Block| neighbor.base() == fluid.base();
+
+        let level = if is_same(chunks.block(coords + Vector3::y())) {
+            Some(1)
+        } else {
+            HORIZONTAL_SIDES
+                .into_iter()
+                .filter_map(|side| {
+                    let coords = coords + SIDE_DELTAS[side].cast();
+                    let neighbor = chunks.block(coords);
+                    let level = neighbor.data().fluid.filter(|_| is_same(neighbor))?.level;
+                    let below = chunks.block(coords - Vector3::y());
+                    (level == 0 || below != Block::AIR && !is_same(below)).then_some(level + 1)
+                })
+                .min()
+        };
+
+        match level.and_then(|level| fluid.data().with_level(level)) {
+            Some(target) if target != block => Some(BlockAction::Place(target)),
+            None if block != Block::AIR => Some(BlockAction::Destroy),
+            _ => None,
+        }
+    }
+}
+
+const HORIZONTAL_SIDES: [Side; 4] = [Side::Front, Side::Right, Side::Back, Side::Left];
+
+const FLOW_SIDES: [Side; 5] = [Side::Top, Side::Front, Side::Right, Side::Back, Side::Left];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::game::world::{block::data::STR_TO_BLOCK, chunk::Chunk};
+    use nalgebra::point;
+
+    fn water(level: u8) -> Block {
+        STR_TO_BLOCK["water"]
+            .data()
+            .with_level(level)
+            .expect("water should have the requested level")
+    }
+
+    fn chunks(blocks: &[(Point3<u8>, Block)]) -> ChunkStore {
+        let mut chunk = Chunk::default();
+        for &(coords, block) in blocks {
+            chunk.apply_unchecked(coords, BlockAction::Place(block));
+        }
+        let mut chunks = ChunkStore::default();
+        chunks.insert(Default::default(), Box::new(chunk), true);
+        chunks
+    }
+
+    fn floor(blocks: &[(Point3<u8>, Block)]) -> ChunkStore {
+        let stone = STR_TO_BLOCK["stone"];
+        let floor = (0..Chunk::DIM as u8)
+            .flat_map(|x| (0..Chunk::DIM as u8).map(move |z| (point![x, 0, z], stone)));
+        chunks(&floor.chain(blocks.iter().copied()).collect::<Vec<_>>())
+    }
+
+    fn flow(chunks: &ChunkStore, coords: Point3<i64>) -> Option<Block> {
+        match FluidStore::flow(chunks, coords)? {
+            BlockAction::Place(block) => Some(block),
+            BlockAction::Destroy => Some(Block::AIR),
+        }
+    }
+
+    #[test]
+    fn source_is_stable() {
+        let chunks = floor(&[(point![8, 1, 8], water(0))]);
+        assert!(flow(&chunks, point![8, 1, 8]).is_none());
+    }
+
+    #[test]
+    fn source_spreads_over_ground() {
+        let chunks = floor(&[(point![8, 1, 8], water(0))]);
+        assert!(flow(&chunks, point![9, 1, 8]) == Some(water(1)));
+        assert!(flow(&chunks, point![8, 1, 7]) == Some(water(1)));
+        assert!(flow(&chunks, point![9, 1, 9]).is_none());
+    }
+
+    #[test]
+    fn flow_weakens_with_distance() {
+        let chunks = floor(&[(point![8, 1, 8], water(0)), (point![9, 1, 8], water(1))]);
+        assert!(flow(&chunks, point![10, 1, 8]) == Some(water(2)));
+    }
+
+    #[test]
+    fn fluid_falls_at_full_strength() {
+        let chunks = floor(&[(point![8, 3, 8], water(2))]);
+        assert!(flow(&chunks, point![8, 2, 8]) == Some(water(1)));
+    }
+
+    #[test]
+    fn flow_does_not_spread_over_air() {
+        let chunks = chunks(&[(point![8, 3, 8], water(1)), (point![8, 2, 8], water(1))]);
+        assert!(flow(&chunks, point![9, 3, 8]).is_none());
+    }
+
+    #[test]
+    fn flow_without_source_drains() {
+        let chunks = floor(&[(point![8, 1, 8], water(2))]);
+        assert!(flow(&chunks, point![8, 1, 8]) == Some(Block::AIR));
+    }
+
+    #[test]
+    fn flow_stops_at_last_level() {
+        let last = (1..u8::MAX)
+            .take_while(|&level| water(0).data().with_level(level).is_some())
+            .last()
+            .expect("water should have flowing levels");
+        let chunks = floor(&[(point![8, 1, 8], water(last))]);
+        assert!(flow(&chunks, point![9, 1, 8]).is_none());
+    }
+
+    #[test]
+    fn load_schedules_unsettled_fluids() {
+        let chunks = floor(&[(point![8, 1, 8], water(0))]);
+        let mut fluids = FluidStore::default();
+        fluids.load(&chunks, &[Default::default()]);
+        let scheduled = fluids
+            .scheduled
+            .values()
+            .flatten()
+            .collect::<FxHashSet<_>>();
+        assert!(scheduled.contains(&point![8, 1, 8]));
+        assert!(scheduled.contains(&point![9, 1, 8]));
+    }
+
+    #[test]
+    fn evict_drops_scheduled_points() {
+        let chunks = floor(&[(point![8, 1, 8], water(0))]);
+        let mut fluids = FluidStore::default();
+        fluids.load(&chunks, &[Default::default()]);
+        fluids.evict(&[Default::default()].into_iter().collect());
+        assert!(fluids.scheduled.is_empty());
+    }
+}
@@ -20,6 +20,10 @@ impl HeightMap {
             })
     }
 
+    pub fn remove(&mut self, coords: Point2<i32>) {
+        self.0.remove(&coords);
+    }
+
     fn load(&mut self, coords: Point3<i32>) -> Option<Point2<i32>> {
         let xz = coords.xz();
         match self.0.entry(xz) {
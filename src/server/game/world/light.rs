@@ -6,10 +6,7 @@ use super::{
         area::BlockAreaLight,
         data::{BlockData, SIDE_DELTAS, Side},
     },
-    chunk::{
-        Chunk, ChunkLight,
-        area::{ChunkArea, ChunkAreaLight},
-    },
+    chunk::{Chunk, ChunkLight},
     height::HeightMap,
 };
 use crate::shared::utils;
@@ -33,28 +30,29 @@ pub struct WorldLight {
 }
 
 impl WorldLight {
-    pub fn chunk_area_light(&self, coords: Point3<i32>) -> ChunkAreaLight {
-        let mut value = ChunkAreaLight::default();
-        for delta in ChunkArea::chunk_deltas() {
-            if let Some(light) = self.get(coords + delta) {
-                for (coords, delta) in ChunkArea::block_deltas(delta) {
-                    value[delta] = light[coords];
-                }
-            }
-        }
-        value
-    }
-
     pub fn block_area_light(&self, coords: Point3<i64>) -> BlockAreaLight {
         BlockAreaLight::from_fn(|delta| self.block_light(coords + delta.cast()))
     }
 
-    pub fn extend_placeholders<P: IntoIterator<Item = Point3<i32>>>(&mut self, points: P) {
-        for coords in points {
-            if self.placeholders.insert(coords) {
-                *self.lights.entry(coords).or_default() |= BlockLight::placeholder();
-            }
-        }
+    pub fn extend_placeholders<P>(&mut self, points: P) -> Vec<Point3<i32>>
+    where
+        P: IntoIterator<Item = Point3<i32>>,
+    {
+        points
+            .into_iter()
+            .filter(|&coords| {
+                let is_inserted = self.placeholders.insert(coords);
+                if is_inserted {
+                    *self.lights.entry(coords).or_default() |= BlockLight::placeholder();
+                }
+                is_inserted
+            })
+            .collect()
+    }
+
+    pub fn remove(&mut self, coords: Point3<i32>) {
+        self.lights.remove(&coords);
+        self.placeholders.remove(&coords);
     }
 
     pub fn par_insert_many(
@@ -88,19 +86,19 @@ impl WorldLight {
         }
     }
 
-    fn get(&self, coords: Point3<i32>) -> Option<&ChunkLight> {
+    pub fn get(&self, coords: Point3<i32>) -> Option<&ChunkLight> {
         self.lights.get(&coords)
     }
 
-    fn entry(&mut self, coords: Point3<i32>) -> Entry<Point3<i32>, ChunkLight> {
-        self.lights.entry(coords)
-    }
-
-    fn block_light(&self, coords: Point3<i64>) -> BlockLight {
+    pub fn block_light(&self, coords: Point3<i64>) -> BlockLight {
         self.get(utils::chunk_coords(coords))
             .map_or_else(Default::default, |light| light[utils::block_coords(coords)])
     }
 
+    fn entry(&mut self, coords: Point3<i32>) -> Entry<Point3<i32>, ChunkLight> {
+        self.lights.entry(coords)
+    }
+
     fn par_insert_many_chunks(
         &mut self,
         chunks: &ChunkStore,
@@ -1,8 +1,11 @@
 pub mod action;
 pub mod block;
 pub mod chunk;
+pub mod fluid;
 pub mod height;
 pub mod light;
+pub mod save;
+pub mod structure;
 
 use self::{
     action::{ActionStore, BlockAction},
@@ -12,19 +15,21 @@ use self::{
         data::{Corner, SIDE_DELTAS, SIDE_MASKS, Side},
     },
     chunk::{
-        Chunk, ChunkDataStore,
+        Chunk, ChunkDataStore, ChunkSnapshot,
         area::{ChunkArea, ChunkAreaLight},
         generator::ChunkGenerator,
     },
+    fluid::FluidStore,
     height::HeightMap,
     light::WorldLight,
+    save::WorldSave,
+    structure::StructureStore,
 };
 use super::player::{Player, WorldArea};
 use crate::{
     client::{ClientEvent, game::world::BlockVertex},
     server::{
-        GroupId, SERVER_CONFIG, ServerEvent, ServerSender,
-        event_loop::{Event, EventHandler},
+        ClientId, GroupId, SERVER_CONFIG, ServerEvent, ServerSender, event_loop::EventHandler,
     },
     shared::{
         bound::Aabb,
@@ -40,173 +45,261 @@ use serde::{Deserialize, Serialize};
 use std::{
     array,
     collections::hash_map::Entry,
-    iter, mem,
+    iter,
     ops::{Index, Range},
 };
 
-#[derive(Default)]
 pub struct World {
     chunks: ChunkStore,
     heights: HeightMap,
-    generator: ChunkGenerator,
+    generator: Box<dyn ChunkGenerator>,
+    structures: StructureStore,
     actions: ActionStore,
     light: WorldLight,
-    hover: Option<BlockIntersection>,
+    fluids: FluidStore,
+    save: WorldSave,
+    clients: FxHashMap<ClientId, WorldClient>,
 }
 
 impl World {
     pub const Y_RANGE: Range<i32> = -4..20;
 
+    pub fn new(seed: Option<u32>) -> Self {
+        let save = WorldSave::new(seed.or(SERVER_CONFIG.world.seed));
+        Self {
+            chunks: Default::default(),
+            heights: Default::default(),
+            generator: <dyn ChunkGenerator>::new(save.seed()),
+            structures: Default::default(),
+            actions: Default::default(),
+            light: Default::default(),
+            fluids: Default::default(),
+            save,
+            clients: Default::default(),
+        }
+    }
+
     fn par_insert_many<P>(&mut self, points: P) -> Vec<Point3<i32>>
     where
         P: IntoParallelIterator<Item = Point3<i32>>,
     {
-        points
+        let points = points.into_par_iter().collect::<Vec<_>>();
+
+        self.structures.par_plan(&*self.generator, &points);
+
+        let inserts = points
             .into_par_iter()
             .filter_map(|coords| Some((coords, self.generate(coords)?)))
             .into_seq_iter()
-            .map(|(coords, chunk)| {
-                self.chunks.insert(coords, chunk);
+            .map(|(coords, (chunk, is_saved))| {
+                self.chunks.insert(coords, chunk, is_saved);
+                self.save.acquire(coords);
                 coords
             })
-            .collect()
+            .collect::<Vec<_>>();
+
+        self.fluids.load(&self.chunks, &inserts);
+
+        inserts
+    }
+
+    fn evict<P: IntoIterator<Item = Point3<i32>>>(&mut self, points: P) {
+        let points = points.into_iter().collect::<FxHashSet<_>>();
+        self.fluids.evict(&points);
+        for coords in points {
+            if let Some((chunk, is_saved)) = self.chunks.evict(coords) {
+                if !is_saved {
+                    self.save.store(coords, Some(&chunk));
+                }
+                self.save.release(coords);
+            }
+            self.heights.remove(coords.xz());
+            self.actions.remove(coords);
+            self.light.remove(coords);
+        }
+    }
+
+    fn evict_structures(&mut self, prev: WorldArea, cur: Option<WorldArea>) {
+        let orphans = |padding| {
+            let prev = prev.padded(padding);
+            match cur {
+                Some(cur) => self.orphans(
+                    prev.par_exclusive_server_points(cur.padded(padding)),
+                    padding,
+                ),
+                None => self.orphans(prev.par_server_points(), padding),
+            }
+        };
+        let planned = orphans(StructureStore::PLANNED_PADDING);
+        let writes = orphans(StructureStore::WRITES_PADDING);
+        self.structures.evict(planned, writes);
     }
 
-    #[rustfmt::skip]
-    fn par_light_up(&mut self, points: &[Point3<i32>]) -> Vec<Point3<i64>> {
-        self.light.extend_placeholders(self.heights.load_placeholders(points.iter().copied()));
-        self.light.par_insert_many(&self.chunks, &self.heights, points)
+    fn par_light_up(&mut self, points: &[Point3<i32>]) -> (Vec<Point3<i64>>, Vec<Point3<i32>>) {
+        let placeholders = self
+            .light
+            .extend_placeholders(self.heights.load_placeholders(points.iter().copied()));
+        (
+            self.light
+                .par_insert_many(&self.chunks, &self.heights, points),
+            placeholders,
+        )
     }
 
     fn apply(
         &mut self,
         coords: Point3<i64>,
         normal: Vector3<i64>,
+        dir: Vector3<f32>,
         action: BlockAction,
-        server_tx: &ServerSender,
-        area: WorldArea,
-        ray: Ray,
     ) {
+        let action = action.oriented(normal, dir);
         let mut branch = Branch::default();
         if branch.apply(&self.chunks, coords, normal, action) {
-            let (block_updates, inserts, removals) = branch.merge(self, area);
-            let updates = self.updates([], block_updates, area, &inserts, &removals);
-            let group_id = GroupId::new(inserts.len() + removals.len() + updates.len());
+            self.merge(branch);
+        }
+    }
 
-            self.handle(&WorldEvent::BlockHoverRequested { ray }, server_tx);
+    pub fn tick(&mut self) {
+        let areas = self
+            .clients
+            .values()
+            .map(|client| client.area)
+            .collect::<Vec<_>>();
+
+        let mut branch = Branch::default();
+        for (coords, action) in self.fluids.tick(&self.chunks) {
+            let chunk_coords = utils::chunk_coords(coords);
+            if Self::Y_RANGE.contains(&chunk_coords.y)
+                && areas.iter().any(|area| area.server_contains(chunk_coords))
+            {
+                branch.insert(coords, action);
+            }
+        }
 
-            self.send_updates(updates, group_id, server_tx);
-            Self::send_unloads(removals, Some(group_id), server_tx);
-            self.send_loads(inserts, group_id, server_tx);
+        if !branch.0.is_empty() {
+            self.merge(branch);
         }
     }
 
-    fn updates(
-        &self,
-        inserts: impl IntoIterator<Item = Point3<i32>>,
-        block_updates: impl IntoIterator<Item = Point3<i64>>,
-        area: WorldArea,
-        loads: &FxHashSet<Point3<i32>>,
-        unloads: &FxHashSet<Point3<i32>>,
-    ) -> FxHashSet<Point3<i32>> {
-        Self::chunk_area_points(inserts)
-            .chain(Self::block_area_points(block_updates).map(utils::chunk_coords))
-            .filter(|coords| {
-                area.client_contains(*coords)
-                    && self.chunks.contains(*coords)
-                    && !loads.contains(coords)
-                    && !unloads.contains(coords)
-            })
-            .collect()
+    fn merge(&mut self, branch: Branch) {
+        let points = branch.points().collect::<Vec<_>>();
+        let (block_updates, inserts, removals, placeholders) = branch.merge(self);
+        let refreshes = inserts.iter().copied().chain(placeholders).collect();
+        let dirty = Self::dirty([], block_updates.iter().copied());
+
+        self.fluids.schedule(&self.chunks, points);
+
+        for id in self.clients.keys().copied().collect::<Vec<_>>() {
+            self.update_hover(id);
+        }
+
+        for client in self.clients.values_mut() {
+            let unloads = removals
+                .iter()
+                .copied()
+                .filter(|coords| client.loaded.contains(coords))
+                .collect();
+            let loads = inserts
+                .iter()
+                .copied()
+                .filter(|&coords| client.area.client_contains(coords))
+                .collect::<Vec<_>>();
+            let updates = client.updates(&dirty, &unloads);
+            let group_id = GroupId::new(loads.len() + unloads.len() + updates.len());
+
+            client.send_deltas(&self.chunks, &self.light, &block_updates);
+            client.send_refreshes(&self.chunks, &self.light, &refreshes);
+            client.send_updates(updates, Some(group_id));
+            client.send_loads(&self.chunks, &self.light, loads, Some(group_id));
+            client.send_unloads(&unloads, Some(group_id));
+        }
     }
 
-    fn send_loads<P>(&self, points: P, group_id: GroupId, server_tx: &ServerSender)
-    where
-        P: IntoIterator<Item = Point3<i32>>,
-    {
-        Self::send_events(
-            points.into_iter().map(|coords| ServerEvent::ChunkLoaded {
-                coords,
-                data: ChunkData::new(&self.chunks, &self.light, coords).into(),
-                group_id: Some(group_id),
-            }),
-            server_tx,
+    fn update_hover(&mut self, id: ClientId) {
+        let Some(client) = self.clients.get(&id) else {
+            return;
+        };
+
+        let ray = client.ray;
+        let hover = ray.cast(SERVER_CONFIG.player.reach.clone()).find(
+            |&BlockIntersection { coords, .. }| {
+                self.chunks
+                    .block(coords)
+                    .data()
+                    .hitboxes(coords)
+                    .any(|hitbox| hitbox.intersects(ray))
+            },
         );
+
+        if client.hover != hover {
+            _ = client.server_tx.send(ServerEvent::BlockHovered(hover.map(
+                |BlockIntersection { coords, .. }| {
+                    BlockHoverData::new(
+                        coords,
+                        self.chunks.block_area(coords),
+                        &self.light.block_area_light(coords),
+                    )
+                },
+            )));
+            self.clients
+                .get_mut(&id)
+                .unwrap_or_else(|| unreachable!())
+                .hover = hover;
+        }
     }
 
-    fn par_send_loads<P>(&self, points: P, server_tx: &ServerSender)
+    fn orphans<P>(&self, points: P, padding: i32) -> Vec<Point3<i32>>
     where
-        P: IntoParallelIterator<Item = Point3<i32>>,
+        P: ParallelIterator<Item = Point3<i32>>,
     {
-        Self::send_events(
-            points
-                .into_par_iter()
-                .map(|coords| ServerEvent::ChunkLoaded {
-                    coords,
-                    data: ChunkData::new(&self.chunks, &self.light, coords).into(),
-                    group_id: None,
-                })
-                .into_seq_iter(),
-            server_tx,
-        );
+        let areas = self
+            .clients
+            .values()
+            .map(|client| client.area.padded(padding))
+            .collect::<Vec<_>>();
+
+        points
+            .filter(|&coords| !areas.iter().any(|area| area.server_contains(coords)))
+            .collect()
     }
 
-    fn send_updates<P>(&self, points: P, group_id: GroupId, server_tx: &ServerSender)
-    where
-        P: IntoIterator<Item = Point3<i32>>,
-    {
-        Self::send_events(
-            points.into_iter().map(|coords| ServerEvent::ChunkUpdated {
-                coords,
-                data: ChunkData::new(&self.chunks, &self.light, coords).into(),
-                group_id: Some(group_id),
-            }),
-            server_tx,
-        );
+    fn refreshes(
+        points: impl IntoIterator<Item = Point3<i32>>,
+        block_updates: &[Point3<i64>],
+    ) -> FxHashSet<Point3<i32>> {
+        points
+            .into_iter()
+            .chain(block_updates.iter().copied().map(utils::chunk_coords))
+            .collect()
     }
 
-    fn par_send_updates<P: IntoParallelIterator<Item = Point3<i32>>>(
-        &self,
-        points: P,
-        server_tx: &ServerSender,
-    ) {
-        Self::send_events(
-            points
-                .into_par_iter()
-                .map(|coords| ServerEvent::ChunkUpdated {
-                    coords,
-                    data: ChunkData::new(&self.chunks, &self.light, coords).into(),
-                    group_id: None,
-                })
-                .into_seq_iter(),
-            server_tx,
-        );
+    fn dirty(
+        inserts: impl IntoIterator<Item = Point3<i32>>,
+        block_updates: impl IntoIterator<Item = Point3<i64>>,
+    ) -> FxHashSet<Point3<i32>> {
+        Self::chunk_area_points(inserts)
+            .chain(Self::block_area_points(block_updates).map(utils::chunk_coords))
+            .collect()
     }
 
-    fn generate(&self, coords: Point3<i32>) -> Option<Box<Chunk>> {
+    fn generate(&self, coords: Point3<i32>) -> Option<(Box<Chunk>, bool)> {
         if self.chunks.contains(coords) {
             None
+        } else if let Some(chunk) = self.save.load(coords) {
+            Some((Box::new(chunk?), true))
         } else {
             let mut chunk = Box::new(self.generator.generate(coords));
+            for (coords, block) in self.structures.writes(coords) {
+                chunk.apply(coords, BlockAction::Place(block));
+            }
             for (coords, action) in self.actions.actions(coords) {
                 chunk.apply_unchecked(coords, action);
             }
-            (!chunk.is_empty()).then_some(chunk)
+            (!chunk.is_empty()).then_some((chunk, false))
         }
     }
 
-    fn send_unloads<P>(points: P, group_id: Option<GroupId>, server_tx: &ServerSender)
-    where
-        P: IntoIterator<Item = Point3<i32>>,
-    {
-        Self::send_events(
-            points
-                .into_iter()
-                .map(|coords| ServerEvent::ChunkUnloaded { coords, group_id }),
-            server_tx,
-        );
-    }
-
     fn chunk_area_points<P>(points: P) -> impl Iterator<Item = Point3<i32>>
     where
         P: IntoIterator<Item = Point3<i32>>,
@@ -225,24 +318,38 @@ impl World {
             .flat_map(|coords| BlockArea::deltas().map(move |delta| coords + delta.cast()))
     }
 
-    fn send_events<E: IntoIterator<Item = ServerEvent>>(events: E, server_tx: &ServerSender) {
-        for event in events {
-            if server_tx.send(event).is_err() {
-                break;
-            }
+    fn chunk_mirrored(chunks: &ChunkStore, light: &WorldLight, coords: Point3<i32>) -> ServerEvent {
+        ServerEvent::ChunkMirrored {
+            coords,
+            snapshot: ChunkSnapshot::new(chunks.get(coords), light.get(coords)).into(),
         }
     }
 }
 
 impl EventHandler<WorldEvent> for World {
-    type Context<'a> = &'a ServerSender;
+    type Context<'a> = ClientId;
 
-    fn handle(&mut self, event: &WorldEvent, server_tx: Self::Context<'_>) {
+    fn handle(&mut self, event: &WorldEvent, id: Self::Context<'_>) {
         match *event {
-            WorldEvent::InitialRenderRequested { area, ray } => {
-                let inserts = self.par_insert_many(area.par_server_points());
+            WorldEvent::InitialRenderRequested {
+                area,
+                ray,
+                ref server_tx,
+            } => {
+                if self.clients.contains_key(&id) {
+                    self.handle(&WorldEvent::ClientDisconnected, id);
+                }
 
-                self.par_light_up(&inserts);
+                let inserts = self.par_insert_many(area.par_server_points());
+                let (block_updates, placeholders) = self.par_light_up(&inserts);
+                let refreshes =
+                    Self::refreshes(inserts.iter().copied().chain(placeholders), &block_updates);
+                let dirty = Self::dirty(inserts, block_updates);
+
+                for client in self.clients.values() {
+                    client.send_refreshes(&self.chunks, &self.light, &refreshes);
+                    client.send_updates(client.updates(&dirty, &Default::default()), None);
+                }
 
                 let mut loads = area
                     .client_points()
@@ -253,89 +360,240 @@ impl EventHandler<WorldEvent> for World {
                     utils::magnitude_squared(coords, utils::chunk_coords(ray.origin))
                 });
 
-                self.handle(&WorldEvent::BlockHoverRequested { ray }, server_tx);
+                self.clients.insert(
+                    id,
+                    WorldClient {
+                        server_tx: server_tx.clone(),
+                        area,
+                        ray,
+                        hover: None,
+                        loaded: Default::default(),
+                        mirrored: Default::default(),
+                    },
+                );
+
+                self.update_hover(id);
 
-                self.par_send_loads(loads, server_tx);
+                self.clients
+                    .get_mut(&id)
+                    .unwrap_or_else(|| unreachable!())
+                    .send_loads(&self.chunks, &self.light, loads, None);
             }
             WorldEvent::WorldAreaChanged { prev, cur, ray } => {
+                let Some(client) = self.clients.get_mut(&id) else {
+                    return;
+                };
+
+                client.area = cur;
+                client.ray = ray;
+
+                let evictions = self.orphans(prev.par_exclusive_server_points(cur), 0);
+                self.evict(evictions.iter().copied());
+                self.evict_structures(prev, Some(cur));
+
                 let inserts = self.par_insert_many(cur.par_exclusive_server_points(prev));
-                let block_updates = self.par_light_up(&inserts);
+                let (block_updates, placeholders) = self.par_light_up(&inserts);
+                let refreshes = Self::refreshes(
+                    evictions
+                        .into_iter()
+                        .chain(inserts.iter().copied())
+                        .chain(placeholders),
+                    &block_updates,
+                );
+                let dirty = Self::dirty(inserts, block_updates);
+
+                self.update_hover(id);
+
+                let client = &self.clients[&id];
                 let loads = cur
                     .exclusive_client_points(prev)
                     .filter(|&coords| self.chunks.contains(coords))
-                    .collect();
+                    .collect::<Vec<_>>();
                 let unloads = prev
                     .exclusive_client_points(cur)
-                    .filter(|&coords| self.chunks.contains(coords))
-                    .collect();
-                let updates = self.updates(inserts, block_updates, cur, &loads, &unloads);
-
-                self.handle(&WorldEvent::BlockHoverRequested { ray }, server_tx);
-
-                Self::send_unloads(unloads, None, server_tx);
-                self.par_send_loads(loads, server_tx);
-                self.par_send_updates(updates, server_tx);
+                    .filter(|coords| client.loaded.contains(coords))
+                    .collect::<FxHashSet<_>>();
+
+                for (&other, client) in &mut self.clients {
+                    client.send_refreshes(&self.chunks, &self.light, &refreshes);
+                    if other == id {
+                        let updates = client.updates(&dirty, &unloads);
+                        client.send_loads(&self.chunks, &self.light, loads.clone(), None);
+                        client.send_unloads(&unloads, None);
+                        client.send_updates(updates, None);
+                    } else {
+                        client.send_updates(client.updates(&dirty, &Default::default()), None);
+                    }
+                }
             }
             WorldEvent::BlockHoverRequested { ray } => {
-                let hover = ray.cast(SERVER_CONFIG.player.reach.clone()).find(
-                    |&BlockIntersection { coords, .. }| {
-                        self.chunks
-                            .block(coords)
-                            .data()
-                            .hitbox(coords)
-                            .intersects(ray)
-                    },
-                );
-
-                if mem::replace(&mut self.hover, hover) != hover {
-                    _ = server_tx.send(ServerEvent::BlockHovered(hover.map(
-                        |BlockIntersection { coords, .. }| {
-                            BlockHoverData::new(
-                                coords,
-                                self.chunks.block_area(coords),
-                                &self.light.block_area_light(coords),
-                            )
-                        },
-                    )));
+                if let Some(client) = self.clients.get_mut(&id) {
+                    client.ray = ray;
+                    self.update_hover(id);
                 }
             }
-            WorldEvent::BlockPlaced { block, area, ray } => {
-                if let Some(BlockIntersection { coords, normal }) = self.hover {
-                    self.apply(
-                        coords + normal,
-                        normal,
-                        BlockAction::Place(block),
-                        server_tx,
-                        area,
-                        ray,
-                    );
+            WorldEvent::BlockPlaced { block, ray } => {
+                if let Some(client) = self.clients.get_mut(&id) {
+                    client.ray = ray;
+                    if let Some(BlockIntersection { coords, normal }) = client.hover {
+                        self.apply(coords + normal, normal, ray.dir, BlockAction::Place(block));
+                    }
                 }
             }
-            WorldEvent::BlockDestroyed { area, ray } => {
-                if let Some(BlockIntersection { coords, normal }) = self.hover {
-                    self.apply(coords, normal, BlockAction::Destroy, server_tx, area, ray);
+            WorldEvent::BlockDestroyed { ray } => {
+                if let Some(client) = self.clients.get_mut(&id) {
+                    client.ray = ray;
+                    if let Some(BlockIntersection { coords, normal }) = client.hover {
+                        self.apply(coords, normal, ray.dir, BlockAction::Destroy);
+                    }
+                }
+            }
+            WorldEvent::ClientDisconnected => {
+                if let Some(client) = self.clients.remove(&id) {
+                    let evictions = self.orphans(client.area.par_server_points(), 0);
+                    self.evict(evictions.iter().copied());
+                    self.evict_structures(client.area, None);
+
+                    let refreshes = Self::refreshes(evictions, &[]);
+                    for client in self.clients.values() {
+                        client.send_refreshes(&self.chunks, &self.light, &refreshes);
+                    }
                 }
             }
         }
     }
 }
 
-#[derive(Default)]
-pub struct ChunkStore(FxHashMap<Point3<i32>, Box<Chunk>>);
+struct WorldClient {
+    server_tx: ServerSender,
+    area: WorldArea,
+    ray: Ray,
+    hover: Option<BlockIntersection>,
+    loaded: FxHashSet<Point3<i32>>,
+    mirrored: FxHashSet<Point3<i32>>,
+}
 
-impl ChunkStore {
-    fn chunk_area(&self, coords: Point3<i32>) -> ChunkArea {
-        let mut value = ChunkArea::default();
-        for delta in ChunkArea::chunk_deltas() {
-            if let Some(chunk) = self.get(coords + delta) {
-                for (coords, delta) in ChunkArea::block_deltas(delta) {
-                    value[delta] = chunk[coords];
-                }
+impl WorldClient {
+    fn updates(
+        &self,
+        dirty: &FxHashSet<Point3<i32>>,
+        unloads: &FxHashSet<Point3<i32>>,
+    ) -> Vec<Point3<i32>> {
+        dirty
+            .iter()
+            .copied()
+            .filter(|coords| self.loaded.contains(coords) && !unloads.contains(coords))
+            .collect()
+    }
+
+    fn send_loads(
+        &mut self,
+        chunks: &ChunkStore,
+        light: &WorldLight,
+        points: Vec<Point3<i32>>,
+        group_id: Option<GroupId>,
+    ) {
+        let loads = points
+            .into_iter()
+            .map(|coords| {
+                let mirrors = World::chunk_area_points([coords])
+                    .filter(|&coords| self.mirrored.insert(coords))
+                    .collect::<Vec<_>>();
+                (coords, mirrors)
+            })
+            .collect::<Vec<_>>();
+
+        self.loaded.extend(loads.iter().map(|&(coords, _)| coords));
+
+        self.send_events(
+            loads
+                .into_par_iter()
+                .flat_map_iter(|(coords, mirrors)| {
+                    mirrors
+                        .into_iter()
+                        .map(|coords| World::chunk_mirrored(chunks, light, coords))
+                        .chain([ServerEvent::ChunkLoaded { coords, group_id }])
+                })
+                .into_seq_iter(),
+        );
+    }
+
+    fn send_unloads(&mut self, points: &FxHashSet<Point3<i32>>, group_id: Option<GroupId>) {
+        self.loaded.retain(|coords| !points.contains(coords));
+
+        let unmirrors = World::chunk_area_points(points.iter().copied())
+            .filter(|&coords| {
+                !World::chunk_area_points([coords]).any(|coords| self.loaded.contains(&coords))
+            })
+            .filter(|coords| self.mirrored.remove(coords))
+            .collect::<Vec<_>>();
+
+        self.send_events(
+            points
+                .iter()
+                .map(|&coords| ServerEvent::ChunkUnloaded { coords, group_id })
+                .chain(
+                    unmirrors
+                        .into_iter()
+                        .map(|coords| ServerEvent::ChunkUnmirrored { coords }),
+                ),
+        );
+    }
+
+    fn send_updates(&self, points: Vec<Point3<i32>>, group_id: Option<GroupId>) {
+        self.send_events(
+            points
+                .into_iter()
+                .map(|coords| ServerEvent::ChunkUpdated { coords, group_id }),
+        );
+    }
+
+    fn send_refreshes(
+        &self,
+        chunks: &ChunkStore,
+        light: &WorldLight,
+        points: &FxHashSet<Point3<i32>>,
+    ) {
+        self.send_events(
+            points
+                .par_iter()
+                .filter(|coords| self.mirrored.contains(coords))
+                .map(|&coords| World::chunk_mirrored(chunks, light, coords))
+                .into_seq_iter(),
+        );
+    }
+
+    fn send_deltas(&self, chunks: &ChunkStore, light: &WorldLight, points: &[Point3<i64>]) {
+        let deltas = points
+            .iter()
+            .copied()
+            .filter(|&coords| self.mirrored.contains(&utils::chunk_coords(coords)))
+            .collect::<FxHashSet<_>>()
+            .into_iter()
+            .map(|coords| (coords, chunks.block(coords), light.block_light(coords)))
+            .collect::<Vec<_>>();
+
+        if !deltas.is_empty() {
+            self.send_events([ServerEvent::BlocksChanged { deltas }]);
+        }
+    }
+
+    fn send_events<E: IntoIterator<Item = ServerEvent>>(&self, events: E) {
+        for event in events {
+            if self.server_tx.send(event).is_err() {
+                break;
             }
         }
-        value
     }
+}
 
+#[derive(Default)]
+pub struct ChunkStore {
+    chunks: FxHashMap<Point3<i32>, Box<Chunk>>,
+    unsaved: FxHashSet<Point3<i32>>,
+}
+
+impl ChunkStore {
     fn block_area(&self, coords: Point3<i64>) -> BlockArea {
         BlockArea::from_fn(|delta| self.block(coords + delta.cast()))
     }
@@ -346,19 +604,31 @@ impl ChunkStore {
     }
 
     fn get(&self, coords: Point3<i32>) -> Option<&Chunk> {
-        self.0.get(&coords).map(|v| &**v)
+        self.chunks.get(&coords).map(|v| &**v)
     }
 
     fn contains(&self, coords: Point3<i32>) -> bool {
-        self.0.contains_key(&coords)
+        self.chunks.contains_key(&coords)
+    }
+
+    fn insert(&mut self, coords: Point3<i32>, chunk: Box<Chunk>, is_saved: bool) {
+        assert!(self.chunks.insert(coords, chunk).is_none());
+        if !is_saved {
+            self.unsaved.insert(coords);
+        }
+    }
+
+    fn evict(&mut self, coords: Point3<i32>) -> Option<(Box<Chunk>, bool)> {
+        let chunk = self.chunks.remove(&coords)?;
+        Some((chunk, !self.unsaved.remove(&coords)))
     }
 
-    fn insert(&mut self, coords: Point3<i32>, chunk: Box<Chunk>) {
-        assert!(self.0.insert(coords, chunk).is_none());
+    fn set_saved(&mut self, coords: Point3<i32>) {
+        self.unsaved.remove(&coords);
     }
 
     fn entry(&mut self, coords: Point3<i32>) -> Entry<Point3<i32>, Box<Chunk>> {
-        self.0.entry(coords)
+        self.chunks.entry(coords)
     }
 }
 
@@ -366,7 +636,7 @@ impl Index<Point3<i32>> for ChunkStore {
     type Output = Chunk;
 
     fn index(&self, coords: Point3<i32>) -> &Self::Output {
-        &self.0[&coords]
+        &self.chunks[&coords]
     }
 }
 
@@ -377,6 +647,7 @@ type Changes = (
     Vec<Point3<i64>>,
     FxHashSet<Point3<i32>>,
     FxHashSet<Point3<i32>>,
+    Vec<Point3<i32>>,
 );
 
 impl Branch {
@@ -402,28 +673,34 @@ impl Branch {
             heights,
             light,
             actions,
+            save,
             ..
         }: &mut World,
-        area: WorldArea,
     ) -> Changes {
         let mut hits = vec![];
+        let mut replacements = FxHashSet::default();
         let mut inserts = FxHashSet::default();
         let mut removals = FxHashSet::default();
 
         for (chunk_coords, actions) in self.0 {
+            let len = hits.len();
+
             match chunks.entry(chunk_coords) {
                 Entry::Occupied(mut entry) => {
                     let chunk = entry.get_mut();
                     for (block_coords, action) in actions {
+                        let is_replacement = chunk[block_coords] != Block::AIR;
                         if chunk.apply(block_coords, action) {
-                            hits.push((utils::coords((chunk_coords, block_coords)), action));
+                            let coords = utils::coords((chunk_coords, block_coords));
+                            if is_replacement && let BlockAction::Place(_) = action {
+                                replacements.insert(coords);
+                            }
+                            hits.push((coords, action));
                         }
                     }
                     if chunk.is_empty() {
                         entry.remove();
-                        if area.client_contains(chunk_coords) {
-                            removals.insert(chunk_coords);
-                        }
+                        removals.insert(chunk_coords);
                     }
                 }
                 Entry::Vacant(entry) => {
@@ -439,27 +716,50 @@ impl Branch {
                             hits.push((utils::coords((chunk_coords, block_coords)), action));
                         }
                         inserts.insert(chunk_coords);
+                        save.acquire(chunk_coords);
                     }
                 }
             }
-        }
 
-        light.extend_placeholders(heights.load_placeholders(inserts.iter().copied()));
+            if hits.len() != len {
+                save.store(chunk_coords, chunks.get(chunk_coords));
+                chunks.set_saved(chunk_coords);
+            }
 
-        inserts.retain(|&coords| area.client_contains(coords));
+            if removals.contains(&chunk_coords) {
+                save.release(chunk_coords);
+            }
+        }
+
+        let placeholders =
+            light.extend_placeholders(heights.load_placeholders(inserts.iter().copied()));
 
         (
             hits.into_iter()
                 .inspect(|&(coords, action)| actions.insert(coords, action))
                 .flat_map(|(coords, action)| {
-                    iter::once(coords).chain(light.apply(chunks, coords, action))
+                    let destroyed = replacements
+                        .contains(&coords)
+                        .then(|| light.apply(chunks, coords, BlockAction::Destroy));
+                    iter::once(coords)
+                        .chain(destroyed.into_iter().flatten())
+                        .chain(light.apply(chunks, coords, action))
                 })
                 .collect(),
             inserts,
             removals,
+            placeholders,
         )
     }
 
+    fn points(&self) -> impl Iterator<Item = Point3<i64>> {
+        self.0.iter().flat_map(|(&chunk_coords, actions)| {
+            actions
+                .keys()
+                .map(move |&block_coords| utils::coords((chunk_coords, block_coords)))
+        })
+    }
+
     fn is_action_valid(
         &mut self,
         chunks: &ChunkStore,
@@ -471,7 +771,7 @@ impl Branch {
             match action {
                 BlockAction::Place(block) => {
                     if let Some(surface) = block.data().valid_surface {
-                        normal == Vector3::y() && chunks.block(coords - normal) == surface
+                        normal == Vector3::y() && chunks.block(coords - normal).base() == surface
                     } else {
                         true
                     }
@@ -499,18 +799,25 @@ impl Branch {
     }
 }
 
-#[derive(Serialize, Deserialize)]
 pub struct ChunkData {
     area: ChunkArea,
     area_light: ChunkAreaLight,
 }
 
 impl ChunkData {
-    fn new(chunks: &ChunkStore, light: &WorldLight, coords: Point3<i32>) -> Self {
-        Self {
-            area: chunks.chunk_area(coords),
-            area_light: light.chunk_area_light(coords),
+    pub fn new<'a, S>(snapshots: S) -> Self
+    where
+        S: IntoIterator<Item = (Vector3<i32>, &'a ChunkSnapshot)>,
+    {
+        let mut area = ChunkArea::default();
+        let mut area_light = ChunkAreaLight::default();
+        for (delta, snapshot) in snapshots {
+            for (coords, delta) in ChunkArea::block_deltas(delta) {
+                area[delta] = snapshot.block(coords);
+                area_light[delta] = snapshot.light(coords);
+            }
         }
+        Self { area, area_light }
     }
 
     pub fn vertices(&self) -> (Vec<BlockVertex>, Vec<BlockVertex>) {
@@ -575,7 +882,9 @@ impl ChunkData {
             let area_light = self.area_light.block_area_light(coords);
             let data = area.block().data();
 
-            if data.requires_blending {
+            if data.fluid.is_some() {
+                transparent_vertices.extend(data.fluid_mesh(coords, area, &area_light));
+            } else if data.requires_blending {
                 transparent_vertices.extend(data.mesh(coords, area, &area_light));
             } else {
                 let is_externally_lit = data.is_externally_lit();
@@ -607,7 +916,7 @@ impl ChunkData {
             let coords = mask.map(|i| [axis, axis, main as i8, secondary as i8][i]);
             let quad = Self::quad(axis >= 0, areas, side, coords);
             let neighbor = Self::quad(axis < Chunk::DIM as i8 - 1, areas, side, coords + abs_delta);
-            if quad == neighbor {
+            if Quad::can_merge(quad, neighbor) {
                 None
             } else if is_negative {
                 neighbor
@@ -624,7 +933,7 @@ impl ChunkData {
         quad: Option<Quad>,
     ) -> usize {
         let mut width = 1;
-        while main + width < Chunk::DIM && quads[index + width] == Some(quad) {
+        while main + width < Chunk::DIM && Quad::can_merge(quads[index + width], Some(quad)) {
             width += 1;
         }
         width
@@ -640,7 +949,7 @@ impl ChunkData {
         let mut height = 1;
         'outer: while secondary + height < Chunk::DIM {
             for main in 0..width {
-                if quads[index + height * Chunk::DIM + main] != Some(quad) {
+                if !Quad::can_merge(quads[index + height * Chunk::DIM + main], Some(quad)) {
                     break 'outer;
                 }
             }
@@ -659,10 +968,11 @@ impl ChunkData {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 struct Quad {
     block: Block,
-    tex_index: u8,
+    is_full: bool,
+    tex_index: u16,
     corner_aos: EnumMap<Corner, u8>,
     corner_lights: EnumMap<Corner, BlockLight>,
 }
@@ -672,12 +982,28 @@ impl Quad {
         let block = area.block();
         let data = block.data();
         let is_externally_lit = data.is_externally_lit();
-        (!data.requires_blending && area.is_side_visible(Some(side))).then(|| Self {
-            block,
-            tex_index: data.tex_index(),
-            corner_aos: area.corner_aos(Some(side), is_externally_lit),
-            corner_lights: area_light.corner_lights(Some(side), *area),
-        })
+        (!data.requires_blending && data.fluid.is_none() && area.is_side_visible(Some(side))).then(
+            || Self {
+                block,
+                is_full: data.is_side_full(side),
+                tex_index: data.tex_index(Some(side)),
+                corner_aos: area.corner_aos(Some(side), is_externally_lit),
+                corner_lights: area_light.corner_lights(Some(side), *area),
+            },
+        )
+    }
+
+    fn can_merge(quad: Option<Option<Self>>, other: Option<Option<Self>>) -> bool {
+        match (quad, other) {
+            (Some(Some(quad)), Some(Some(other))) => {
+                quad.is_full
+                    && other.is_full
+                    && quad.tex_index == other.tex_index
+                    && quad.corner_aos == other.corner_aos
+                    && quad.corner_lights == other.corner_lights
+            }
+            _ => quad == other,
+        }
     }
 
     fn vertices(
@@ -698,17 +1024,9 @@ impl Quad {
     }
 }
 
-impl PartialEq for Quad {
-    fn eq(&self, other: &Self) -> bool {
-        self.tex_index == other.tex_index
-            && self.corner_aos == other.corner_aos
-            && self.corner_lights == other.corner_lights
-    }
-}
-
-#[derive(Clone, Copy, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BlockHoverData {
-    pub hitbox: Aabb,
+    pub hitboxes: Vec<Aabb>,
     pub brightness: BlockLight,
 }
 
@@ -716,7 +1034,7 @@ impl BlockHoverData {
     fn new(coords: Point3<i64>, area: BlockArea, area_light: &BlockAreaLight) -> Self {
         let data = area.block().data();
         Self {
-            hitbox: data.hitbox(coords),
+            hitboxes: data.hitboxes(coords).collect(),
             brightness: Self::brightness(area, area_light),
         }
     }
@@ -729,10 +1047,16 @@ impl BlockHoverData {
     }
 }
 
+#[derive(Deserialize)]
+pub struct WorldConfig {
+    seed: Option<u32>,
+}
+
 pub enum WorldEvent {
     InitialRenderRequested {
         area: WorldArea,
         ray: Ray,
+        server_tx: ServerSender,
     },
     WorldAreaChanged {
         prev: WorldArea,
@@ -744,38 +1068,35 @@ pub enum WorldEvent {
     },
     BlockPlaced {
         block: Block,
-        area: WorldArea,
         ray: Ray,
     },
     BlockDestroyed {
-        area: WorldArea,
         ray: Ray,
     },
+    ClientDisconnected,
 }
 
 impl WorldEvent {
-    pub fn new(event: &Event, &Player { prev, cur, ray }: &Player) -> Option<Self> {
+    pub fn new(
+        event: &ClientEvent,
+        &Player { prev, cur, ray, .. }: &Player,
+        server_tx: &ServerSender,
+    ) -> Option<Self> {
         match *event {
-            Event::Client(ClientEvent::InitialRenderRequested { .. }) => {
-                Some(Self::InitialRenderRequested { area: cur, ray })
-            }
-            Event::Client(ClientEvent::PlayerPositionChanged { .. }) if cur != prev => {
-                Some(Self::WorldAreaChanged { prev, cur, ray })
-            }
-            Event::Client(ClientEvent::PlayerPositionChanged { .. }) => {
-                Some(Self::BlockHoverRequested { ray })
-            }
-            Event::Client(ClientEvent::PlayerOrientationChanged { .. }) => {
-                Some(Self::BlockHoverRequested { ray })
-            }
-            Event::Client(ClientEvent::BlockPlaced(block)) => Some(Self::BlockPlaced {
-                block,
+            ClientEvent::InitialRenderRequested { .. } => Some(Self::InitialRenderRequested {
                 area: cur,
                 ray,
+                server_tx: server_tx.clone(),
             }),
-            Event::Client(ClientEvent::BlockDestroyed) => {
-                Some(Self::BlockDestroyed { area: cur, ray })
+            ClientEvent::PlayerPositionChanged { .. } if cur != prev => {
+                Some(Self::WorldAreaChanged { prev, cur, ray })
+            }
+            ClientEvent::PlayerPositionChanged { .. } => Some(Self::BlockHoverRequested { ray }),
+            ClientEvent::PlayerOrientationChanged { .. } => Some(Self::BlockHoverRequested { ray }),
+            ClientEvent::BlockPlaced(block) if block.is_valid() => {
+                Some(Self::BlockPlaced { block, ray })
             }
+            ClientEvent::BlockDestroyed => Some(Self::BlockDestroyed { ray }),
             _ => None,
         }
     }
@@ -0,0 +1,600 @@
+use super::{
+    World,
+    block::{BlockRemap, data::BLOCK_NAMES},
+    chunk::Chunk,
+};
+use crate::server::SERVER_CONFIG;
+use nalgebra::{Point2, Point3};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    collections::{BTreeMap, VecDeque, hash_map::Entry},
+    fs::{self, File},
+    io::{self, prelude::*},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+type RegionCache = VecDeque<(Point2<i32>, Arc<Mutex<Region>>)>;
+
+pub struct WorldSave {
+    path: PathBuf,
+    seed: u32,
+    remap: Option<BlockRemap>,
+    regions: Mutex<FxHashMap<Point2<i32>, Arc<Mutex<Region>>>>,
+    cache: Mutex<RegionCache>,
+    refs: FxHashMap<Point2<i32>, usize>,
+}
+
+impl WorldSave {
+    const VERSION: u32 = 1;
+    const LEVEL_PATH: &str = "level.bin";
+    const REGIONS_PATH: &str = "regions";
+    const CACHE_LEN: usize = 8;
+
+    pub fn new(seed: Option<u32>) -> Self {
+        Self::open(&SERVER_CONFIG.save.path, seed)
+    }
+
+    fn open<P: AsRef<Path>>(path: P, seed: Option<u32>) -> Self {
+        let path = path.as_ref();
+        let level_path = path.join(Self::LEVEL_PATH);
+        let (seed, blocks) = match Self::read_level(&level_path) {
+            Some(LevelData {
+                version,
+                seed: saved_seed,
+                mut blocks,
+            }) => {
+                if let Some(seed) = seed
+                    && seed != saved_seed
+                {
+                    eprintln!(
+                        "save {path:?} has seed {saved_seed}, ignoring requested seed {seed}"
+                    );
+                }
+                let len = blocks.len();
+                blocks.extend(
+                    BLOCK_NAMES
+                        .iter()
+                        .filter(|str| !blocks.contains(str))
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                );
+                if blocks.len() != len {
+                    Self::write(
+                        &level_path,
+                        &LevelData {
+                            version,
+                            seed: saved_seed,
+                            blocks: blocks.clone(),
+                        },
+                    );
+                }
+                (saved_seed, blocks)
+            }
+            None => {
+                let seed = seed.unwrap_or_else(rand::random);
+                fs::create_dir_all(path.join(Self::REGIONS_PATH))
+                    .unwrap_or_else(|e| panic!("failed to create {path:?}: {e}"));
+                let blocks = BLOCK_NAMES.to_vec();
+                Self::write(
+                    &level_path,
+                    &LevelData {
+                        version: Self::VERSION,
+                        seed,
+                        blocks: blocks.clone(),
+                    },
+                );
+                (seed, blocks)
+            }
+        };
+
+        Self {
+            path: path.into(),
+            seed,
+            remap: BlockRemap::new(&blocks),
+            regions: Default::default(),
+            cache: Default::default(),
+            refs: Default::default(),
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn load(&self, coords: Point3<i32>) -> Option<Option<Chunk>> {
+        let chunk: Option<Chunk> = self
+            .region(Region::coords(coords), false)?
+            .lock()
+            .unwrap_or_else(|_| unreachable!())
+            .read(coords)?;
+        Some(match &self.remap {
+            Some(remap) => chunk.map(|chunk| {
+                Chunk::from_blocks(chunk.block_store().map(|block| remap.to_local(block)))
+            }),
+            None => chunk,
+        })
+    }
+
+    pub fn store(&self, coords: Point3<i32>, chunk: Option<&Chunk>) {
+        let region = self
+            .region(Region::coords(coords), true)
+            .unwrap_or_else(|| unreachable!());
+        let mut region = region.lock().unwrap_or_else(|_| unreachable!());
+        let blocks = chunk.map(|chunk| {
+            let blocks = chunk.block_store().compacted();
+            match &self.remap {
+                Some(remap) => {
+                    blocks.map(|block| remap.to_remote(block).unwrap_or_else(|| unreachable!()))
+                }
+                None => blocks,
+            }
+        });
+        region.write(coords, &blocks);
+    }
+
+    pub fn acquire(&mut self, coords: Point3<i32>) {
+        *self.refs.entry(Region::coords(coords)).or_default() += 1;
+    }
+
+    pub fn release(&mut self, coords: Point3<i32>) {
+        let coords = Region::coords(coords);
+        if let Entry::Occupied(mut entry) = self.refs.entry(coords) {
+            *entry.get_mut() -= 1;
+            if *entry.get() == 0 {
+                entry.remove();
+                if let Some(region) = self
+                    .regions
+                    .get_mut()
+                    .unwrap_or_else(|_| unreachable!())
+                    .remove(&coords)
+                {
+                    Self::cache(
+                        self.cache.get_mut().unwrap_or_else(|_| unreachable!()),
+                        coords,
+                        region,
+                    );
+                }
+            }
+        }
+    }
+
+    fn region(&self, coords: Point2<i32>, create: bool) -> Option<Arc<Mutex<Region>>> {
+        let mut regions = self.regions.lock().unwrap_or_else(|_| unreachable!());
+        if let Some(region) = regions.get(&coords) {
+            return Some(region.clone());
+        }
+
+        let mut cache = self.cache.lock().unwrap_or_else(|_| unreachable!());
+        let region = match cache.iter().position(|&(cached, _)| cached == coords) {
+            Some(i) => cache.remove(i).unwrap_or_else(|| unreachable!()).1,
+            None => Arc::new(Mutex::new(Region::open(self.region_path(coords), create)?)),
+        };
+        if self.refs.contains_key(&coords) {
+            regions.insert(coords, region.clone());
+        } else {
+            Self::cache(&mut cache, coords, region.clone());
+        }
+        Some(region)
+    }
+
+    fn cache(cache: &mut RegionCache, coords: Point2<i32>, region: Arc<Mutex<Region>>) {
+        cache.push_back((coords, region));
+        if cache.len() > Self::CACHE_LEN {
+            cache.pop_front();
+        }
+    }
+
+    fn region_path(&self, coords: Point2<i32>) -> PathBuf {
+        self.path
+            .join(Self::REGIONS_PATH)
+            .join(format!("{}_{}.bin", coords.x, coords.y))
+    }
+
+    fn read_level(path: &Path) -> Option<LevelData> {
+        let version = Self::read::<u32>(path)?;
+        assert!(
+            version == Self::VERSION,
+            "save {path:?} has unsupported version {version}, expected {}",
+            Self::VERSION,
+        );
+        Self::read(path)
+    }
+
+    fn read<T: DeserializeOwned>(path: impl AsRef<Path>) -> Option<T> {
+        let path = path.as_ref();
+        match fs::read(path) {
+            Ok(bytes) => Some(
+                bincode::deserialize(&bytes)
+                    .unwrap_or_else(|e| panic!("failed to deserialize {path:?}: {e}")),
+            ),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => panic!("failed to open {path:?}: {e}"),
+        }
+    }
+
+    fn write<T: Serialize>(path: impl AsRef<Path>, value: &T) {
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("tmp");
+        let bytes = bincode::serialize(value)
+            .unwrap_or_else(|e| panic!("failed to serialize {path:?}: {e}"));
+        fs::write(&tmp_path, bytes)
+            .and_then(|_| fs::rename(&tmp_path, path))
+            .unwrap_or_else(|e| panic!("failed to write {path:?}: {e}"));
+    }
+}
+
+struct Region {
+    path: PathBuf,
+    file: File,
+    slots: Box<[Slot]>,
+    free: BTreeMap<u64, u64>,
+    len: u64,
+}
+
+impl Region {
+    const DIM: usize = 32;
+    const HEIGHT: usize = World::Y_RANGE.end.abs_diff(World::Y_RANGE.start) as usize;
+    const SLOT_COUNT: usize = Self::DIM * Self::DIM * Self::HEIGHT;
+    const HEADER_LEN: u64 = (Self::SLOT_COUNT * Slot::LEN) as u64;
+
+    fn open(path: PathBuf, create: bool) -> Option<Self> {
+        match File::options().read(true).write(true).open(&path) {
+            Ok(mut file) => {
+                let mut header = vec![0; Self::HEADER_LEN as usize];
+                file.read_exact(&mut header)
+                    .unwrap_or_else(|e| panic!("failed to read {path:?}: {e}"));
+                let slots = header
+                    .chunks_exact(Slot::LEN)
+                    .map(Slot::from_bytes)
+                    .collect::<Box<_>>();
+                let (free, len) = Self::free(&slots);
+                Some(Self {
+                    path,
+                    file,
+                    slots,
+                    free,
+                    len,
+                })
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound && create => {
+                let mut file = File::options()
+                    .read(true)
+                    .write(true)
+                    .create_new(true)
+                    .open(&path)
+                    .unwrap_or_else(|e| panic!("failed to create {path:?}: {e}"));
+                file.write_all(&vec![0; Self::HEADER_LEN as usize])
+                    .unwrap_or_else(|e| panic!("failed to write {path:?}: {e}"));
+                Some(Self {
+                    path,
+                    file,
+                    slots: vec![Default::default(); Self::SLOT_COUNT].into(),
+                    free: Default::default(),
+                    len: Self::HEADER_LEN,
+                })
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => panic!("failed to open {path:?}: {e}"),
+        }
+    }
+
+    fn read<T: DeserializeOwned>(&mut self, coords: Point3<i32>) -> Option<T> {
+        let Slot { offset, len } = self.slots[Self::index(coords)];
+        (len != 0).then(|| {
+            let mut bytes = vec![0; len as usize];
+            self.file
+                .seek(io::SeekFrom::Start(offset))
+                .and_then(|_| self.file.read_exact(&mut bytes))
+                .unwrap_or_else(|e| panic!("failed to read {:?}: {e}", self.path));
+            bincode::deserialize(&bytes)
+                .unwrap_or_else(|e| panic!("failed to deserialize {:?}: {e}", self.path))
+        })
+    }
+
+    fn write<T: Serialize>(&mut self, coords: Point3<i32>, value: &T) {
+        let index = Self::index(coords);
+        let bytes = bincode::serialize(value)
+            .unwrap_or_else(|e| panic!("failed to serialize {:?}: {e}", self.path));
+        let prev = self.slots[index];
+        let len = bytes.len() as u64;
+        let slot = Slot {
+            offset: if len <= prev.len as u64 {
+                prev.offset
+            } else {
+                self.allocate(len)
+            },
+            len: len as u32,
+        };
+
+        self.file
+            .seek(io::SeekFrom::Start(slot.offset))
+            .and_then(|_| self.file.write_all(&bytes))
+            .and_then(|_| {
+                self.file
+                    .seek(io::SeekFrom::Start((index * Slot::LEN) as u64))
+            })
+            .and_then(|_| self.file.write_all(&slot.to_bytes()))
+            .unwrap_or_else(|e| panic!("failed to write {:?}: {e}", self.path));
+
+        self.slots[index] = slot;
+        if slot.offset == prev.offset {
+            self.deallocate(prev.offset + len, prev.len as u64 - len);
+        } else {
+            self.deallocate(prev.offset, prev.len as u64);
+        }
+    }
+
+    fn allocate(&mut self, len: u64) -> u64 {
+        match self
+            .free
+            .iter()
+            .find(|&(_, &free_len)| free_len >= len)
+            .map(|(&offset, &free_len)| (offset, free_len))
+        {
+            Some((offset, free_len)) => {
+                self.free.remove(&offset);
+                if free_len > len {
+                    self.free.insert(offset + len, free_len - len);
+                }
+                offset
+            }
+            None => {
+                self.len += len;
+                self.len - len
+            }
+        }
+    }
+
+    fn deallocate(&mut self, mut offset: u64, mut len: u64) {
+        if len == 0 {
+            return;
+        }
+        if let Some(next_len) = self.free.remove(&(offset + len)) {
+            len += next_len;
+        }
+        if let Some((&prev, &prev_len)) = self.free.range(..offset).next_back()
+            && prev + prev_len == offset
+        {
+            self.free.remove(&prev);
+            offset = prev;
+            len += prev_len;
+        }
+
+        if offset + len == self.len {
+            self.len = offset;
+            self.file
+                .set_len(offset)
+                .unwrap_or_else(|e| panic!("failed to write {:?}: {e}", self.path));
+        } else {
+            self.free.insert(offset, len);
+        }
+    }
+
+    fn free(slots: &[Slot]) -> (BTreeMap<u64, u64>, u64) {
+        let mut used = slots
+            .iter()
+            .filter(|slot| slot.len != 0)
+            .map(|slot| (slot.offset, slot.offset + slot.len as u64))
+            .collect::<Vec<_>>();
+        used.sort_unstable();
+
+        let mut free = BTreeMap::new();
+        let mut end = Self::HEADER_LEN;
+        for (start, stop) in used {
+            if start > end {
+                free.insert(end, start - end);
+            }
+            end = end.max(stop);
+        }
+        (free, end)
+    }
+
+    fn coords(coords: Point3<i32>) -> Point2<i32> {
+        coords.xz().map(|c| c.div_floor(Self::DIM as i32))
+    }
+
+    fn index(coords: Point3<i32>) -> usize {
+        let [x, z] = coords
+            .xz()
+            .map(|c| c.rem_euclid(Self::DIM as i32) as usize)
+            .into();
+        let y = (coords.y - World::Y_RANGE.start) as usize;
+        (x * Self::DIM + z) * Self::HEIGHT + y
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Slot {
+    offset: u64,
+    len: u32,
+}
+
+impl Slot {
+    const LEN: usize = 12;
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let (offset, len) = bytes.split_at(8);
+        Self {
+            offset: u64::from_le_bytes(offset.try_into().unwrap_or_else(|_| unreachable!())),
+            len: u32::from_le_bytes(len.try_into().unwrap_or_else(|_| unreachable!())),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; Self::LEN] {
+        let mut bytes = [0; Self::LEN];
+        bytes[..8].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.len.to_le_bytes());
+        bytes
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct LevelData {
+    version: u32,
+    seed: u32,
+    blocks: Vec<Arc<str>>,
+}
+
+#[derive(Deserialize)]
+pub struct SaveConfig {
+    path: PathBuf,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::point;
+    use rustc_hash::FxHashSet;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("crustcrab-save-{}-{name}", std::process::id()));
+            _ = fs::remove_dir_all(&path);
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn open_keeps_saved_seed() {
+        let dir = TempDir::new("seed");
+        assert_eq!(WorldSave::open(&dir.0, Some(1)).seed(), 1);
+        assert_eq!(WorldSave::open(&dir.0, Some(2)).seed(), 1);
+        assert_eq!(WorldSave::open(&dir.0, None).seed(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "unsupported version")]
+    fn open_reports_unsupported_version() {
+        let dir = TempDir::new("version");
+        fs::create_dir_all(&dir.0).unwrap();
+        WorldSave::write(
+            dir.0.join(WorldSave::LEVEL_PATH),
+            &(WorldSave::VERSION + 1, "future"),
+        );
+        WorldSave::open(&dir.0, None);
+    }
+
+    #[test]
+    fn slot_round_trips_bytes() {
+        let slot = Slot::from_bytes(
+            &Slot {
+                offset: u64::MAX - 1,
+                len: 42,
+            }
+            .to_bytes(),
+        );
+        assert_eq!((slot.offset, slot.len), (u64::MAX - 1, 42));
+    }
+
+    #[test]
+    fn region_index_is_unique_per_chunk() {
+        let indices = (-1..1)
+            .flat_map(|x| World::Y_RANGE.flat_map(move |y| (-1..1).map(move |z| point![x, y, z])))
+            .map(Region::index)
+            .collect::<FxHashSet<_>>();
+        assert_eq!(indices.len(), 4 * Region::HEIGHT);
+        assert!(indices.iter().all(|&index| index < Region::SLOT_COUNT));
+    }
+
+    #[test]
+    fn region_reads_missing_slots_as_none() {
+        let dir = TempDir::new("missing");
+        fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join("region");
+        assert!(Region::open(path.clone(), false).is_none());
+        let mut region = Region::open(path, true).unwrap();
+        assert_eq!(region.read::<Vec<u8>>(point![0, 0, 0]), None);
+    }
+
+    #[test]
+    fn region_writes_survive_reopen() {
+        let dir = TempDir::new("reopen");
+        fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join("region");
+        let mut region = Region::open(path.clone(), true).unwrap();
+        region.write(point![0, 0, 0], &vec![1u8; 16]);
+        region.write(point![-1, 1, 5], &vec![2u8; 8]);
+        drop(region);
+
+        let mut region = Region::open(path, false).unwrap();
+        assert_eq!(region.read(point![0, 0, 0]), Some(vec![1u8; 16]));
+        assert_eq!(region.read(point![-1, 1, 5]), Some(vec![2u8; 8]));
+        assert_eq!(region.read::<Vec<u8>>(point![0, 1, 0]), None);
+    }
+
+    #[test]
+    fn region_rewrites_in_place_or_appends() {
+        let dir = TempDir::new("rewrite");
+        fs::create_dir_all(&dir.0).unwrap();
+        let mut region = Region::open(dir.0.join("region"), true).unwrap();
+        region.write(point![0, 0, 0], &vec![1u8; 16]);
+        region.write(point![1, 0, 0], &vec![2u8; 16]);
+        let len = region.len;
+
+        region.write(point![0, 0, 0], &vec![3u8; 8]);
+        assert_eq!(region.len, len);
+        region.write(point![0, 0, 0], &vec![4u8; 32]);
+        assert!(region.len > len);
+
+        assert_eq!(region.read(point![0, 0, 0]), Some(vec![4u8; 32]));
+        assert_eq!(region.read(point![1, 0, 0]), Some(vec![2u8; 16]));
+    }
+
+    #[test]
+    fn region_reuses_freed_space() {
+        let dir = TempDir::new("reuse");
+        fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join("region");
+        let mut region = Region::open(path.clone(), true).unwrap();
+        region.write(point![0, 0, 0], &vec![1u8; 16]);
+        region.write(point![1, 0, 0], &vec![2u8; 16]);
+        region.write(point![0, 0, 0], &vec![3u8; 32]);
+        let len = region.len;
+        drop(region);
+
+        let mut region = Region::open(path, false).unwrap();
+        assert_eq!(region.len, len);
+        region.write(point![2, 0, 0], &vec![4u8; 8]);
+        assert_eq!(region.len, len);
+        assert_eq!(region.read(point![0, 0, 0]), Some(vec![3u8; 32]));
+        assert_eq!(region.read(point![1, 0, 0]), Some(vec![2u8; 16]));
+        assert_eq!(region.read(point![2, 0, 0]), Some(vec![4u8; 8]));
+    }
+
+    #[test]
+    fn region_stays_bounded_under_rewrites() {
+        let dir = TempDir::new("bounded");
+        fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join("region");
+        let mut region = Region::open(path.clone(), true).unwrap();
+        for i in 0..64 {
+            region.write(point![0, 0, 0], &vec![1u8; 16 + i % 3 * 16]);
+            region.write(point![1, 0, 0], &vec![2u8; 16 + i % 5 * 16]);
+        }
+        assert!(region.len <= Region::HEADER_LEN + 256);
+        assert_eq!(fs::metadata(path).unwrap().len(), region.len);
+    }
+
+    #[test]
+    fn unreferenced_regions_are_cached() {
+        let dir = TempDir::new("cache");
+        let save = WorldSave::open(&dir.0, Some(1));
+        save.store(point![0, 0, 0], None);
+        let region = save.region(point![0, 0], false).unwrap();
+        assert!(Arc::ptr_eq(
+            &region,
+            &save.region(point![0, 0], false).unwrap()
+        ));
+    }
+}
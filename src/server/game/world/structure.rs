@@ -0,0 +1,96 @@
+use super::{
+    World,
+    block::Block,
+    chunk::{
+        Chunk,
+        generator::{ChunkGenerator, structure::Structure},
+    },
+};
+use crate::shared::utils;
+use nalgebra::{Point3, Vector3, vector};
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{collections::hash_map::Entry, ops::Range};
+
+#[derive(Default)]
+pub struct StructureStore {
+    planned: FxHashSet<Point3<i32>>,
+    writes: FxHashMap<Point3<i32>, ChunkWrites>,
+}
+
+type ChunkWrites = FxHashMap<Point3<u8>, (Point3<i32>, Block)>;
+
+impl StructureStore {
+    const PADDING: usize = Structure::MAX_EXTENT.div_ceil(Chunk::DIM);
+    const AXIS_RANGE: Range<i32> = -(Self::PADDING as i32)..1 + Self::PADDING as i32;
+    pub const PLANNED_PADDING: i32 = 2 * Self::PADDING as i32;
+    pub const WRITES_PADDING: i32 = 2 * Self::PLANNED_PADDING;
+
+    pub fn writes(&self, coords: Point3<i32>) -> impl Iterator<Item = (Point3<u8>, Block)> {
+        self.writes
+            .get(&coords)
+            .into_iter()
+            .flatten()
+            .map(|(&coords, &(_, block))| (coords, block))
+    }
+
+    pub fn par_plan(&mut self, generator: &dyn ChunkGenerator, points: &[Point3<i32>]) {
+        let origins = points
+            .iter()
+            .flat_map(|&coords| Self::chunk_deltas().map(move |delta| coords + delta))
+            .filter(|coords| World::Y_RANGE.contains(&coords.y) && !self.planned.contains(coords))
+            .collect::<FxHashSet<_>>();
+
+        let structures = origins
+            .into_par_iter()
+            .map(|origin| (origin, generator.structures(origin)))
+            .collect::<Vec<_>>();
+
+        for (origin, blocks) in structures {
+            self.planned.insert(origin);
+            for (coords, block) in blocks {
+                self.insert(origin, coords, block);
+            }
+        }
+    }
+
+    pub fn evict<P, W>(&mut self, planned: P, writes: W)
+    where
+        P: IntoIterator<Item = Point3<i32>>,
+        W: IntoIterator<Item = Point3<i32>>,
+    {
+        for coords in planned {
+            self.planned.remove(&coords);
+        }
+        for coords in writes {
+            self.writes.remove(&coords);
+        }
+    }
+
+    fn insert(&mut self, origin: Point3<i32>, coords: Point3<i64>, block: Block) {
+        let chunk_coords = utils::chunk_coords(coords);
+        if World::Y_RANGE.contains(&chunk_coords.y) {
+            match self
+                .writes
+                .entry(chunk_coords)
+                .or_default()
+                .entry(utils::block_coords(coords))
+            {
+                Entry::Occupied(mut entry) => {
+                    if <[_; 3]>::from(origin) <= entry.get().0.into() {
+                        entry.insert((origin, block));
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert((origin, block));
+                }
+            }
+        }
+    }
+
+    fn chunk_deltas() -> impl Iterator<Item = Vector3<i32>> {
+        Self::AXIS_RANGE.flat_map(|dx| {
+            Self::AXIS_RANGE.flat_map(move |dy| Self::AXIS_RANGE.map(move |dz| vector![dx, dy, dz]))
+        })
+    }
+}
@@ -1,14 +1,24 @@
+pub mod codec;
 pub(crate) mod event_loop;
 pub(crate) mod game;
 pub(crate) mod ticker;
 
 use self::{
+    codec::{Compression, FrameReader},
     event_loop::{EventLoop, EventLoopConfig},
     game::{
         Game,
         clock::{ClockState, Time},
         player::PlayerConfig,
-        world::{BlockHoverData, ChunkData},
+        world::{
+            BlockHoverData, WorldConfig,
+            block::{
+                Block, BlockLight, BlockRemap,
+                data::{BLOCK_HASHES, BLOCK_NAMES},
+            },
+            chunk::{ChunkSnapshot, generator::GeneratorConfig},
+            save::SaveConfig,
+        },
     },
 };
 use crate::{
@@ -16,33 +26,48 @@ use crate::{
     shared::utils,
 };
 use crossbeam_channel::{Receiver, Sender};
-use nalgebra::Point3;
+use nalgebra::{Point3, Vector3};
+use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, LazyLock};
+use std::{
+    io::{self, Read},
+    sync::{Arc, LazyLock},
+};
 use uuid::Uuid;
 
 pub struct Server {
     event_loop: EventLoop,
+    seed: Option<u32>,
 }
 
 impl Server {
-    pub fn new(server_tx: ServerSender, client_rx: Receiver<ClientEvent>) -> Self {
+    pub fn new(client_rx: Receiver<(ClientId, ClientEvent)>, seed: Option<u32>) -> Self {
         Self {
-            event_loop: EventLoop::new(server_tx, client_rx),
+            event_loop: EventLoop::new(client_rx),
+            seed,
         }
     }
 
     pub fn run(&mut self) {
-        self.event_loop.run(Game::default());
+        self.event_loop.run(Game::new(self.seed));
     }
 }
 
 #[derive(Serialize, Deserialize)]
 pub enum ServerEvent {
     TimeUpdated(Time),
+    ChunkMirrored {
+        coords: Point3<i32>,
+        snapshot: Arc<ChunkSnapshot>,
+    },
+    ChunkUnmirrored {
+        coords: Point3<i32>,
+    },
+    BlocksChanged {
+        deltas: Vec<(Point3<i64>, Block, BlockLight)>,
+    },
     ChunkLoaded {
         coords: Point3<i32>,
-        data: Arc<ChunkData>,
         group_id: Option<GroupId>,
     },
     ChunkUnloaded {
@@ -51,19 +76,53 @@ pub enum ServerEvent {
     },
     ChunkUpdated {
         coords: Point3<i32>,
-        data: Arc<ChunkData>,
         group_id: Option<GroupId>,
     },
     BlockHovered(Option<BlockHoverData>),
+    PlayerJoined {
+        id: ClientId,
+        name: String,
+        origin: Point3<f32>,
+        dir: Vector3<f32>,
+    },
+    PlayerMoved {
+        id: ClientId,
+        origin: Point3<f32>,
+        dir: Vector3<f32>,
+    },
+    PlayerLeft(ClientId),
+    Ping,
+    SessionClosed(String),
     #[serde(skip)]
     ClientDisconnected,
+    #[serde(skip)]
+    Disconnected,
+    #[serde(skip)]
+    Connected,
 }
 
 impl ServerEvent {
-    fn has_priority(&self) -> bool {
+    pub fn remap_blocks(&mut self, remap: &BlockRemap) {
+        match self {
+            Self::ChunkMirrored { snapshot, .. } => Arc::make_mut(snapshot).remap(remap),
+            Self::BlocksChanged { deltas } => {
+                for (_, block, _) in deltas {
+                    *block = remap.to_local(*block);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn has_priority(&self) -> bool {
         !matches!(
             self,
-            Self::ChunkLoaded { .. } | Self::ChunkUnloaded { .. } | Self::ChunkUpdated { .. }
+            Self::ChunkMirrored { .. }
+                | Self::ChunkUnmirrored { .. }
+                | Self::BlocksChanged { .. }
+                | Self::ChunkLoaded { .. }
+                | Self::ChunkUnloaded { .. }
+                | Self::ChunkUpdated { .. }
         )
     }
 }
@@ -83,6 +142,138 @@ impl GroupId {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct Handshake {
+    version: u32,
+    blocks: Vec<(Arc<str>, u64)>,
+    compression: Compression,
+}
+
+impl Handshake {
+    const VERSION: u32 = 1;
+
+    pub fn new(compression: Compression) -> Self {
+        Self {
+            version: Self::VERSION,
+            blocks: BLOCK_NAMES
+                .iter()
+                .cloned()
+                .zip(BLOCK_HASHES.iter().copied())
+                .collect(),
+            compression,
+        }
+    }
+
+    pub fn read<R: Read>(reader: &mut FrameReader<R>) -> io::Result<Result<Self, String>> {
+        let bytes = reader.read_frame()?;
+        Ok(match reader.decode::<u32>(&bytes) {
+            Ok(version) if version != Self::VERSION => Err(format!(
+                "unsupported protocol version {version}, expected {}",
+                Self::VERSION,
+            )),
+            _ => reader.decode(&bytes).map_err(|e| e.to_string()),
+        })
+    }
+
+    pub fn check(&self, peer: &Self) -> Result<(), String> {
+        if self.version != peer.version {
+            Err(format!(
+                "protocol version mismatch: ours is {}, peer's is {}",
+                self.version, peer.version,
+            ))
+        } else {
+            let hashes = self.blocks.iter().cloned().collect::<FxHashMap<_, _>>();
+            let peer_hashes = peer.blocks.iter().cloned().collect::<FxHashMap<_, _>>();
+            let added = Self::missing(&peer.blocks, &hashes);
+            let removed = Self::missing(&self.blocks, &peer_hashes);
+            if !added.is_empty() || !removed.is_empty() {
+                return Err(format!(
+                    "block registry mismatch: peer adds [{added}], peer lacks [{removed}]"
+                ));
+            }
+
+            match peer
+                .blocks
+                .iter()
+                .find(|(block, hash)| hashes.get(block).is_some_and(|other| other != hash))
+            {
+                Some((block, hash)) => Err(format!(
+                    "block registry mismatch on \"{block}\": ours is {:016x}, peer's is {hash:016x}",
+                    hashes[block],
+                )),
+                None => Ok(()),
+            }
+        }
+    }
+
+    fn missing(blocks: &[(Arc<str>, u64)], hashes: &FxHashMap<Arc<str>, u64>) -> String {
+        blocks
+            .iter()
+            .filter(|(block, _)| !hashes.contains_key(block))
+            .map(|(block, _)| format!("\"{block}\""))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn block_remap(&self) -> Option<BlockRemap> {
+        BlockRemap::new(
+            &self
+                .blocks
+                .iter()
+                .map(|(block, _)| block.clone())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    pub fn compression(&self, peer: &Self) -> Compression {
+        self.compression.negotiate(peer.compression)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ClientId(Uuid);
+
+impl ClientId {
+    pub fn random() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+#[derive(Default)]
+pub struct Clients(FxHashMap<ClientId, ServerSender>);
+
+impl Clients {
+    pub fn get(&self, id: ClientId) -> Option<&ServerSender> {
+        self.0.get(&id)
+    }
+
+    pub fn send(&self, id: ClientId, event: ServerEvent) {
+        if let Some(server_tx) = self.get(id) {
+            _ = server_tx.send(event);
+        }
+    }
+
+    pub fn broadcast<F: Fn() -> ServerEvent>(&self, f: F) {
+        for server_tx in self.0.values() {
+            _ = server_tx.send(f());
+        }
+    }
+
+    pub fn broadcast_except<F: Fn() -> ServerEvent>(&self, id: ClientId, f: F) {
+        for (_, server_tx) in self.0.iter().filter(|&(&other, _)| other != id) {
+            _ = server_tx.send(f());
+        }
+    }
+
+    fn insert(&mut self, id: ClientId, server_tx: ServerSender) {
+        self.0.insert(id, server_tx);
+    }
+
+    fn remove(&mut self, id: ClientId) -> Option<ServerSender> {
+        self.0.remove(&id)
+    }
+}
+
 #[derive(Clone)]
 pub enum ServerSender {
     Proxy(EventLoopProxy),
@@ -93,12 +284,6 @@ pub enum ServerSender {
 }
 
 impl ServerSender {
-    pub fn disconnected() -> Self {
-        let (priority_tx, _) = crossbeam_channel::unbounded();
-        let (tx, _) = crossbeam_channel::unbounded();
-        Self::Sender { priority_tx, tx }
-    }
-
     pub fn send(&self, event: ServerEvent) -> Result<(), ServerEvent> {
         match self {
             Self::Proxy(proxy) => proxy.send_event(event).map_err(|e| e.0),
@@ -122,7 +307,106 @@ struct ServerConfig {
     event_loop: EventLoopConfig,
     player: PlayerConfig,
     clock: ClockState,
+    world: WorldConfig,
+    generator: GeneratorConfig,
+    save: SaveConfig,
 }
 
 static SERVER_CONFIG: LazyLock<ServerConfig> =
     LazyLock::new(|| utils::deserialize("assets/config/server.toml"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::codec::FrameWriter;
+
+    fn peer(blocks: Vec<(Arc<str>, u64)>) -> Handshake {
+        Handshake {
+            version: Handshake::VERSION,
+            blocks,
+            compression: Compression::None,
+        }
+    }
+
+    #[test]
+    fn check_accepts_reordered_registry() {
+        let handshake = Handshake::new(Compression::None);
+        let mut blocks = handshake.blocks.clone();
+        blocks.reverse();
+
+        let peer = peer(blocks);
+        assert!(handshake.check(&peer).is_ok());
+        assert!(peer.block_remap().is_some());
+        assert!(handshake.block_remap().is_none());
+    }
+
+    #[test]
+    fn check_rejects_added_and_removed_blocks() {
+        let handshake = Handshake::new(Compression::None);
+        let mut blocks = handshake.blocks.clone();
+        blocks.retain(|(block, _)| &**block != "stone");
+        blocks.push(("unknown".into(), 0));
+
+        let e = handshake.check(&peer(blocks)).unwrap_err();
+        assert!(e.contains("peer adds [\"unknown\"]"));
+        assert!(e.contains("peer lacks [\"stone\"]"));
+    }
+
+    #[test]
+    fn check_rejects_unknown_server_block() {
+        let handshake = Handshake::new(Compression::None);
+        let mut blocks = handshake.blocks.clone();
+        blocks.push(("unknown".into(), 0));
+
+        assert!(handshake.check(&peer(blocks)).is_err());
+    }
+
+    #[test]
+    fn check_rejects_changed_block() {
+        let handshake = Handshake::new(Compression::None);
+        let mut blocks = handshake.blocks.clone();
+        blocks.reverse();
+        blocks[0].1 ^= 1;
+
+        assert!(handshake.check(&peer(blocks)).is_err());
+    }
+
+    #[test]
+    fn check_rejects_other_version() {
+        let handshake = Handshake::new(Compression::None);
+        let mut peer = peer(handshake.blocks.clone());
+        peer.version += 1;
+
+        assert!(handshake.check(&peer).is_err());
+    }
+
+    #[test]
+    fn read_round_trips_handshake() {
+        let mut writer = FrameWriter::new(vec![]);
+        writer
+            .write(&Handshake::new(Compression::Deflate), Compression::None)
+            .unwrap();
+        let bytes = writer.into_inner();
+
+        let peer = Handshake::read(&mut FrameReader::new(&*bytes))
+            .unwrap()
+            .unwrap();
+        assert!(Handshake::new(Compression::Deflate).check(&peer).is_ok());
+        assert_eq!(peer.compression, Compression::Deflate);
+    }
+
+    #[test]
+    fn read_reports_unsupported_version() {
+        let mut writer = FrameWriter::new(vec![]);
+        writer
+            .write(&(Handshake::VERSION + 1, "future"), Compression::None)
+            .unwrap();
+        let bytes = writer.into_inner();
+
+        let e = Handshake::read(&mut FrameReader::new(&*bytes))
+            .unwrap()
+            .err()
+            .unwrap();
+        assert!(e.contains("unsupported protocol version"));
+    }
+}
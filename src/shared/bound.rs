@@ -4,8 +4,8 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, Default, Serialize, Deserialize)]
 pub struct Aabb {
-    min: Point3<f32>,
-    max: Point3<f32>,
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
 }
 
 impl Aabb {
@@ -13,13 +13,24 @@ impl Aabb {
         Self::from_corners(origin, origin + diagonal)
     }
 
-    fn from_corners(a: Point3<f32>, b: Point3<f32>) -> Self {
+    pub fn from_corners(a: Point3<f32>, b: Point3<f32>) -> Self {
         Self {
             min: a.inf(&b),
             max: a.sup(&b),
         }
     }
 
+    pub fn translate(self, delta: Vector3<f32>) -> Self {
+        Self {
+            min: self.min + delta,
+            max: self.max + delta,
+        }
+    }
+
+    pub fn contains(self, point: Point3<f32>) -> bool {
+        (0..3).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+
     pub fn pad(mut self, amount: f32) -> Self {
         self.min.apply(|c| *c -= amount);
         self.max.apply(|c| *c += amount);
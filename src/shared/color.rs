@@ -8,7 +8,7 @@ use std::{
 };
 
 #[repr(transparent)]
-#[derive(Clone, Copy, PartialEq, Default, Zeroable, Pod, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Default, Hash, Zeroable, Pod, Deserialize)]
 pub struct Rgb<T>([T; 3]);
 
 impl<T> Rgb<T> {